@@ -0,0 +1,393 @@
+use crate::save::{
+    read_metadata, read_save, SaveError, AREAS_FOLDER, CHARACTER_FILE, MAP_FILE, METADATA_FILE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Version of the archive layout written by `export_save`.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// File extension used for exported saves.
+pub const ARCHIVE_EXTENSION: &str = "rpgsave";
+
+/// Marks a JSON file as a rustpg save archive.
+const ARCHIVE_MAGIC: &str = "rustpg-save";
+
+/// Files packed into an archive, next to the tiles of every map in the
+/// areas folder. Backups stay on the machine they belong to.
+const ARCHIVE_FILES: [&str; 3] = [CHARACTER_FILE, MAP_FILE, METADATA_FILE];
+
+/// Everything that can be wrong with an archive file itself.
+#[derive(Debug, PartialEq)]
+pub enum ArchiveError {
+    NotAnArchive,
+    UnsupportedVersion(u32),
+    ChecksumMismatch { expected: String, found: String },
+    MissingFile(String),
+    UnexpectedFile(String),
+    InvalidName(String),
+    NameTaken(String),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::NotAnArchive => write!(f, "not a rustpg save archive"),
+            ArchiveError::UnsupportedVersion(version) => write!(
+                f,
+                "archive version {} is newer than supported version {}",
+                version, ARCHIVE_FORMAT_VERSION
+            ),
+            ArchiveError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch (expected {}, found {}), the archive is damaged",
+                expected, found
+            ),
+            ArchiveError::MissingFile(file) => write!(f, "archive is missing {}", file),
+            ArchiveError::UnexpectedFile(file) => {
+                write!(f, "archive contains unknown file {}", file)
+            }
+            ArchiveError::InvalidName(name) => write!(f, "'{}' is not a valid save name", name),
+            ArchiveError::NameTaken(name) => write!(f, "a save named '{}' already exists", name),
+        }
+    }
+}
+
+/// A save folder packed into a single JSON file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaveArchive {
+    format: String,
+    format_version: u32,
+    character_name: String,
+    save_version: u32,
+    game_version: String,
+    build: String,
+    checksum: String,
+    files: BTreeMap<String, String>,
+}
+
+impl SaveArchive {
+    pub fn character_name(&self) -> &str {
+        &self.character_name
+    }
+}
+
+/// Packs the save in `save_folder` into `archive_path`.
+///
+/// The save is loaded first so a broken save is never exported.
+pub fn export_save(save_folder: &Path, archive_path: &Path) -> Result<SaveArchive, SaveError> {
+    let (_, _, character_name) = read_save(save_folder)?;
+    // Makes sure meta.json exists for saves written before it was added
+    read_metadata(save_folder)?;
+
+    let mut files = BTreeMap::new();
+    for file in ARCHIVE_FILES {
+        files.insert(
+            file.to_string(),
+            fs::read_to_string(save_folder.join(file))?,
+        );
+    }
+    let areas_folder = save_folder.join(AREAS_FOLDER);
+    if areas_folder.is_dir() {
+        for entry in fs::read_dir(&areas_folder)? {
+            let file = format!("{}/{}", AREAS_FOLDER, entry?.file_name().to_string_lossy());
+            if is_area_file(&file) {
+                files.insert(file.clone(), fs::read_to_string(save_folder.join(&file))?);
+            }
+        }
+    }
+    let raw: Value = serde_json::from_str(&files[CHARACTER_FILE])?;
+    let save_version = raw.get("save_version").and_then(Value::as_u64).unwrap_or(1) as u32;
+
+    let archive = SaveArchive {
+        format: ARCHIVE_MAGIC.to_string(),
+        format_version: ARCHIVE_FORMAT_VERSION,
+        character_name,
+        save_version,
+        game_version: option_env!("VERSION")
+            .unwrap_or("unknown version")
+            .to_string(),
+        build: option_env!("BUILD_NUMBER")
+            .unwrap_or("unknown build")
+            .to_string(),
+        checksum: checksum(&files),
+        files,
+    };
+    fs::write(archive_path, serde_json::to_string(&archive)?)?;
+    Ok(archive)
+}
+
+/// Reads an archive and checks its format, version, checksum and contents.
+pub fn read_archive(archive_path: &Path) -> Result<SaveArchive, SaveError> {
+    let data = fs::read_to_string(archive_path)?;
+    let raw: Value = serde_json::from_str(&data).map_err(|_| ArchiveError::NotAnArchive)?;
+    if raw.get("format").and_then(Value::as_str) != Some(ARCHIVE_MAGIC) {
+        return Err(ArchiveError::NotAnArchive.into());
+    }
+    let format_version = raw
+        .get("format_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32;
+    if format_version == 0 || format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(ArchiveError::UnsupportedVersion(format_version).into());
+    }
+
+    let archive: SaveArchive = serde_json::from_value(raw)?;
+    let found = checksum(&archive.files);
+    if found != archive.checksum {
+        return Err(ArchiveError::ChecksumMismatch {
+            expected: archive.checksum,
+            found,
+        }
+        .into());
+    }
+    if let Some(file) = archive
+        .files
+        .keys()
+        .find(|file| !ARCHIVE_FILES.contains(&file.as_str()) && !is_area_file(file))
+    {
+        return Err(ArchiveError::UnexpectedFile(file.clone()).into());
+    }
+    for file in [CHARACTER_FILE, MAP_FILE] {
+        if !archive.files.contains_key(file) {
+            return Err(ArchiveError::MissingFile(file.to_string()).into());
+        }
+    }
+    check_save_name(&archive.character_name)?;
+    Ok(archive)
+}
+
+/// Unpacks `archive` into `saves_path/save_name`.
+///
+/// The files are unpacked next to the target and loaded before anything is
+/// moved into place, so a bad archive never leaves a half-imported save. An
+/// existing save with the same name is only replaced when `overwrite` is set.
+pub fn import_save(
+    archive: &SaveArchive,
+    saves_path: &Path,
+    save_name: &str,
+    overwrite: bool,
+) -> Result<PathBuf, SaveError> {
+    check_save_name(save_name)?;
+    let target = saves_path.join(save_name);
+    if target.exists() && !overwrite {
+        return Err(ArchiveError::NameTaken(save_name.to_string()).into());
+    }
+
+    let staging = saves_path.join(format!(".import-{}", save_name));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    for (file, data) in &archive.files {
+        let path = staging.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)?;
+    }
+    if let Err(e) = read_save(&staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::rename(&staging, &target)?;
+    Ok(target)
+}
+
+/// First of `name`, `name (2)`, `name (3)`, ... that isn't taken in
+/// `saves_path`.
+pub fn free_save_name(saves_path: &Path, name: &str) -> String {
+    let mut candidate = name.to_string();
+    let mut number = 2;
+    while saves_path.join(&candidate).exists() {
+        candidate = format!("{} ({})", name, number);
+        number += 1;
+    }
+    candidate
+}
+
+/// Save names become folder names, so they must be a single plain path
+/// component.
+pub(crate) fn check_save_name(name: &str) -> Result<(), ArchiveError> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.starts_with('.') => Ok(()),
+        _ => Err(ArchiveError::InvalidName(name.to_string())),
+    }
+}
+
+/// Whether `file` is `maps/<name>.txt` with a name that stays inside the
+/// areas folder.
+fn is_area_file(file: &str) -> bool {
+    file.strip_prefix(AREAS_FOLDER)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .and_then(|rest| rest.strip_suffix(".txt"))
+        .is_some_and(|name| check_save_name(name).is_ok())
+}
+
+/// 64-bit FNV-1a over every file name and its contents, in name order.
+fn checksum(files: &BTreeMap<String, String>) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (name, data) in files {
+        let length = (data.len() as u64).to_le_bytes();
+        for byte in name.bytes().chain([0]).chain(length).chain(data.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+    use crate::player::Player;
+    use crate::save::{save_game, temp_save_folder};
+
+    fn export_sample(folder: &Path) -> PathBuf {
+        let save_folder = folder.join("Saves").join("Packed");
+        fs::create_dir_all(&save_folder).unwrap();
+        let mut player = Player::new();
+        player.add_item_to_inventory(100017, 4);
+        save_game(&player, &Map::new(30, 30, 42), &save_folder, "Packed").unwrap();
+
+        let archive_path = folder.join(format!("Packed.{}", ARCHIVE_EXTENSION));
+        export_save(&save_folder, &archive_path).unwrap();
+        archive_path
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let folder = temp_save_folder("archive_round_trip");
+        let archive_path = export_sample(&folder);
+
+        let archive = read_archive(&archive_path).unwrap();
+        assert_eq!(archive.character_name(), "Packed");
+        assert_eq!(archive.save_version, crate::save::SAVE_VERSION);
+
+        let other_saves = folder.join("OtherMachine");
+        fs::create_dir_all(&other_saves).unwrap();
+        let imported = import_save(&archive, &other_saves, "Packed", false).unwrap();
+        let (player, game_map, name) = read_save(&imported).unwrap();
+        assert_eq!(name, "Packed");
+        assert_eq!(player.inventory[&100017], 4);
+        assert_eq!((game_map.width, game_map.height), (30, 30));
+        assert!(!other_saves.join(".import-Packed").exists());
+
+        let _ = fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn name_collisions_need_a_new_name_or_overwrite() {
+        let folder = temp_save_folder("archive_collision");
+        let archive_path = export_sample(&folder);
+        let archive = read_archive(&archive_path).unwrap();
+        let saves_path = folder.join("Saves");
+
+        assert!(matches!(
+            import_save(&archive, &saves_path, "Packed", false),
+            Err(SaveError::Archive(ArchiveError::NameTaken(_)))
+        ));
+        assert_eq!(free_save_name(&saves_path, "Packed"), "Packed (2)");
+        assert_eq!(free_save_name(&saves_path, "Unused"), "Unused");
+
+        let imported = import_save(&archive, &saves_path, "Packed (2)", false).unwrap();
+        assert_eq!(free_save_name(&saves_path, "Packed"), "Packed (3)");
+        assert!(import_save(&archive, &saves_path, "Packed (2)", true).is_ok());
+        assert!(read_save(&imported).is_ok());
+
+        let _ = fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn damaged_or_foreign_archives_are_rejected() {
+        let folder = temp_save_folder("archive_damaged");
+        let archive_path = export_sample(&folder);
+        let data = fs::read_to_string(&archive_path).unwrap();
+
+        fs::write(&archive_path, data.replace("100017", "100018")).unwrap();
+        assert!(matches!(
+            read_archive(&archive_path),
+            Err(SaveError::Archive(ArchiveError::ChecksumMismatch { .. }))
+        ));
+
+        fs::write(
+            &archive_path,
+            data.replace("\"format_version\":1", "\"format_version\":99"),
+        )
+        .unwrap();
+        assert!(matches!(
+            read_archive(&archive_path),
+            Err(SaveError::Archive(ArchiveError::UnsupportedVersion(99)))
+        ));
+
+        fs::write(&archive_path, "{\"player\": {}}").unwrap();
+        assert!(matches!(
+            read_archive(&archive_path),
+            Err(SaveError::Archive(ArchiveError::NotAnArchive))
+        ));
+
+        let _ = fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn other_maps_travel_with_the_archive() {
+        let folder = temp_save_folder("archive_areas");
+        let save_folder = folder.join("Saves").join("Traveller");
+        fs::create_dir_all(&save_folder).unwrap();
+        let mut areas = crate::world::Areas::default();
+        let mut game_map = Map::new(60, 60, 42);
+        let gate = game_map.portals[0].clone();
+        game_map.move_player(&crate::map::Direction::Right);
+        areas.travel(&mut game_map, gate.x, gate.y).unwrap();
+        crate::save::save_world(&Player::new(), &game_map, &areas, &save_folder, "Traveller")
+            .unwrap();
+
+        let archive_path = folder.join("Traveller.rpgsave");
+        export_save(&save_folder, &archive_path).unwrap();
+        let archive = read_archive(&archive_path).unwrap();
+        assert!(archive.files.contains_key("maps/town.txt"));
+
+        let other_saves = folder.join("OtherMachine");
+        fs::create_dir_all(&other_saves).unwrap();
+        let imported = import_save(&archive, &other_saves, "Traveller", false).unwrap();
+        let (_, game_map, _) = read_save(&imported).unwrap();
+        assert_eq!(game_map.name, crate::map::TOWN);
+
+        let _ = fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn area_files_cannot_escape_the_areas_folder() {
+        assert!(is_area_file("maps/town.txt"));
+        for file in [
+            "maps/../character.json",
+            "maps/.hidden.txt",
+            "maps/town",
+            "town.txt",
+        ] {
+            assert!(!is_area_file(file), "{:?} should be rejected", file);
+        }
+    }
+
+    #[test]
+    fn save_names_cannot_escape_the_saves_folder() {
+        assert!(check_save_name("Packed (2)").is_ok());
+        for name in ["", ".", "..", "../Packed", "a/b", "/tmp", ".import-Packed"] {
+            assert!(
+                check_save_name(name).is_err(),
+                "{:?} should be rejected",
+                name
+            );
+        }
+    }
+}
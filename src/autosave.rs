@@ -0,0 +1,195 @@
+use crate::player::Player;
+use std::env;
+
+/// Moves between autosaves when `RUSTPG_AUTOSAVE_MOVES` is not set.
+pub const DEFAULT_AUTOSAVE_MOVES: u32 = 100;
+
+/// Which events trigger an autosave.
+///
+/// `RUSTPG_AUTOSAVE_MOVES` sets the move interval (`0` turns it off) and
+/// `RUSTPG_AUTOSAVE_EVENTS` is a comma separated list of `victory`,
+/// `levelup` and `quest`, or `none`. Everything is on by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutosaveConfig {
+    pub every_moves: u32,
+    pub after_victory: bool,
+    pub on_level_up: bool,
+    pub on_quest_complete: bool,
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        AutosaveConfig {
+            every_moves: DEFAULT_AUTOSAVE_MOVES,
+            after_victory: true,
+            on_level_up: true,
+            on_quest_complete: true,
+        }
+    }
+}
+
+impl AutosaveConfig {
+    pub fn from_env() -> Self {
+        Self::parse(
+            env::var("RUSTPG_AUTOSAVE_MOVES").ok().as_deref(),
+            env::var("RUSTPG_AUTOSAVE_EVENTS").ok().as_deref(),
+        )
+    }
+
+    fn parse(moves: Option<&str>, events: Option<&str>) -> Self {
+        let mut config = AutosaveConfig::default();
+        if let Some(moves) = moves.and_then(|moves| moves.trim().parse().ok()) {
+            config.every_moves = moves;
+        }
+        if let Some(events) = events {
+            let events: Vec<String> = events
+                .split(',')
+                .map(|event| event.trim().to_lowercase())
+                .collect();
+            config.after_victory = events.iter().any(|event| event == "victory");
+            config.on_level_up = events.iter().any(|event| event == "levelup");
+            config.on_quest_complete = events.iter().any(|event| event == "quest");
+        }
+        config
+    }
+}
+
+/// Watches the player between turns and decides when the game loop should
+/// autosave.
+pub struct AutosaveTracker {
+    config: AutosaveConfig,
+    moves_since_save: u32,
+    victory_pending: bool,
+    levels: (i32, i32),
+    completed_quests: usize,
+}
+
+impl AutosaveTracker {
+    pub fn new(config: AutosaveConfig, player: &Player) -> Self {
+        AutosaveTracker {
+            config,
+            moves_since_save: 0,
+            victory_pending: false,
+            levels: player_levels(player),
+            completed_quests: completed_quests(player),
+        }
+    }
+
+    pub fn record_move(&mut self) {
+        self.moves_since_save += 1;
+    }
+
+    pub fn record_victory(&mut self) {
+        self.victory_pending = true;
+    }
+
+    /// Returns why the game should autosave now, if it should, and starts
+    /// counting again from the current state.
+    pub fn check(&mut self, player: &Player) -> Option<&'static str> {
+        let reason = if self.config.on_quest_complete
+            && completed_quests(player) > self.completed_quests
+        {
+            Some("quest completed")
+        } else if self.config.on_level_up && player_levels(player) != self.levels {
+            Some("level up")
+        } else if self.config.after_victory && self.victory_pending {
+            Some("victory")
+        } else if self.config.every_moves > 0 && self.moves_since_save >= self.config.every_moves {
+            Some("moves")
+        } else {
+            None
+        };
+
+        // Move the baseline on even when nothing fired, so events that are
+        // turned off don't pile up and fire later
+        let moves_since_save = self.moves_since_save;
+        self.reset(player);
+        if reason.is_none() {
+            self.moves_since_save = moves_since_save;
+        }
+        reason
+    }
+
+    /// Call after any save so the triggers count from that save.
+    pub fn reset(&mut self, player: &Player) {
+        self.moves_since_save = 0;
+        self.victory_pending = false;
+        self.levels = player_levels(player);
+        self.completed_quests = completed_quests(player);
+    }
+}
+
+fn player_levels(player: &Player) -> (i32, i32) {
+    (player.level, player.total_level())
+}
+
+fn completed_quests(player: &Player) -> usize {
+    player.quests.iter().filter(|q| q.is_completed()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quest::starting_quest;
+
+    #[test]
+    fn config_reads_move_interval_and_events() {
+        assert_eq!(AutosaveConfig::parse(None, None), AutosaveConfig::default());
+
+        let config = AutosaveConfig::parse(Some("25"), Some("victory, quest"));
+        assert_eq!(config.every_moves, 25);
+        assert!(config.after_victory);
+        assert!(!config.on_level_up);
+        assert!(config.on_quest_complete);
+
+        let config = AutosaveConfig::parse(Some("0"), Some("none"));
+        assert_eq!(config.every_moves, 0);
+        assert!(!config.after_victory && !config.on_level_up && !config.on_quest_complete);
+    }
+
+    #[test]
+    fn tracker_fires_on_each_trigger() {
+        let mut player = Player::new();
+        player.add_quest(starting_quest());
+        let config = AutosaveConfig {
+            every_moves: 3,
+            ..AutosaveConfig::default()
+        };
+        let mut tracker = AutosaveTracker::new(config, &player);
+
+        tracker.record_move();
+        tracker.record_move();
+        assert_eq!(tracker.check(&player), None);
+        tracker.record_move();
+        assert_eq!(tracker.check(&player), Some("moves"));
+        assert_eq!(tracker.check(&player), None);
+
+        tracker.record_victory();
+        assert_eq!(tracker.check(&player), Some("victory"));
+
+        player
+            .skills
+            .get_mut("Attack")
+            .unwrap()
+            .add_experience(1000.0);
+        assert_eq!(tracker.check(&player), Some("level up"));
+
+        player.complete_quest(1);
+        assert_eq!(tracker.check(&player), Some("quest completed"));
+        assert_eq!(tracker.check(&player), None);
+    }
+
+    #[test]
+    fn disabled_triggers_never_fire() {
+        let mut player = Player::new();
+        let config = AutosaveConfig::parse(Some("0"), Some("none"));
+        let mut tracker = AutosaveTracker::new(config, &player);
+
+        for _ in 0..500 {
+            tracker.record_move();
+        }
+        tracker.record_victory();
+        player.level_up();
+        assert_eq!(tracker.check(&player), None);
+    }
+}
@@ -1,17 +1,15 @@
 // Core game components
-use crate::enemy::Enemy;
-use crate::skill::{combat_xp_calculation, AttackType};
+use crate::damage::{player_attack, roll_hit, Hit};
+use crate::enemy::{Ability, Enemy};
 use crate::player::Player;
+use crate::skill::{combat_xp_calculation, AttackType};
+use crate::status::{describe_tick, StatusEffect, StatusKind};
 
 // Inventory system
 use crate::inventory::display_and_handle_inventory;
 
 // Item system
-use crate::items::{
-    create_items,
-    calculate_loot,
-    LootTable,
-};
+use crate::items::{create_items, roll_loot, LootTable};
 
 // External crates
 use log::{debug, info};
@@ -19,20 +17,486 @@ use rand::Rng;
 use std::collections::HashMap;
 use std::io::{self, Write};
 
+/// How a fight started by `handle_combat` ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatOutcome {
+    Victory,
+    Defeat,
+    Fled,
+    /// Every enemy ran off before any of them was beaten.
+    EnemiesFled,
+}
+
+/// What the player does on their turn. Targets are indices into the fight's
+/// enemies; one that is already down gives way to the first enemy standing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatAction {
+    Main {
+        target: usize,
+    },
+    /// Winds up a blow that lands next round on the target and the enemies
+    /// beside it.
+    Charge {
+        target: usize,
+    },
+    /// A spell that strikes every enemy standing.
+    Spell,
+    Flee,
+}
+
+/// Something that happened in a round, in the order it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CombatEvent {
+    PlayerAttacked {
+        target: usize,
+        attack: AttackType,
+        hit: Hit,
+    },
+    EnemyDefeated(usize),
+    ChargeStarted,
+    /// The player tried to cast a spell without knowing any magic.
+    SpellFailed,
+    /// A plain attack when `ability` is `None`, else the blow the ability
+    /// calls for.
+    EnemyAttacked {
+        enemy: usize,
+        ability: Option<Ability>,
+        hit: Hit,
+    },
+    EnemyBlocked(usize),
+    EnemyHealed {
+        enemy: usize,
+        amount: i32,
+    },
+    EnemyFled(usize),
+    /// `enemy` called for help and `joined` came.
+    HelpArrived {
+        enemy: usize,
+        joined: usize,
+    },
+    StatusApplied {
+        target: Fighter,
+        kind: StatusKind,
+    },
+    /// A status effect changed the health of `target` as its turn began.
+    StatusTick {
+        target: Fighter,
+        kind: StatusKind,
+        change: i32,
+    },
+    /// `target` was stunned and lost its turn.
+    Stunned(Fighter),
+    FleeFailed,
+    Fled,
+    /// XP and loot from every enemy defeated. Fights also end this way when
+    /// the last enemies standing flee after others were beaten.
+    Victory {
+        xp: i32,
+        loot: HashMap<u32, u32>,
+    },
+    /// The last enemies standing fled and none had been beaten.
+    EnemiesFled,
+    Defeat,
+}
+
+/// Who a status effect is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fighter {
+    Player,
+    Enemy(usize),
+}
+
+/// Chance that running away works.
+const FLEE_CHANCE: f64 = 0.5;
+
+/// Experience the player gets for every enemy defeated.
+const VICTORY_XP: i32 = 10;
+
+/// Energy a turn costs. An enemy gains its speed in energy every round, so
+/// one with a speed of 20 acts twice a round and one of 5 every other round.
+const TURN_ENERGY: i32 = 10;
+
+/// Most enemies a fight can grow to when they call for help.
+const MAX_ENEMIES: usize = 4;
+
+/// Defense a blocking enemy adds on top of its own.
+const BLOCK_DEFENSE: i32 = 20;
+
+/// What poisoned blows, rending bites, and charged attacks that land leave
+/// behind.
+const POISON: StatusEffect = StatusEffect {
+    kind: StatusKind::Poison,
+    potency: 2,
+    turns: 3,
+};
+const BLEED: StatusEffect = StatusEffect {
+    kind: StatusKind::Bleed,
+    potency: 2,
+    turns: 3,
+};
+
+/// Critical heavy hits and critical spells stun for a turn.
+const STUN: StatusEffect = StatusEffect {
+    kind: StatusKind::Stun,
+    potency: 0,
+    turns: 1,
+};
+
+/// What the fight keeps track of for each enemy besides the enemy itself.
+#[derive(Debug, Clone, Copy, Default)]
+struct Foe {
+    energy: i32,
+    blocking: bool,
+    fled: bool,
+}
+
+/// The rules of a fight against a group of enemies, with no input or output
+/// of its own, so whole fights can be played by tests, scripts or the
+/// terminal alike.
+pub struct CombatState<'a> {
+    pub enemies: Vec<Enemy>,
+    foes: Vec<Foe>,
+    loot_tables: &'a HashMap<String, LootTable>,
+    charging: Option<usize>,
+    attack_counts: HashMap<AttackType, usize>,
+    outcome: Option<CombatOutcome>,
+}
+
+impl<'a> CombatState<'a> {
+    pub fn new(enemies: Vec<Enemy>, loot_tables: &'a HashMap<String, LootTable>) -> Self {
+        CombatState {
+            foes: vec![Foe::default(); enemies.len()],
+            enemies,
+            loot_tables,
+            charging: None,
+            attack_counts: HashMap::new(),
+            outcome: None,
+        }
+    }
+
+    /// Whether the player wound up a charged attack last round.
+    pub fn is_charging(&self) -> bool {
+        self.charging.is_some()
+    }
+
+    /// How the fight ended, or `None` while it goes on.
+    pub fn outcome(&self) -> Option<CombatOutcome> {
+        self.outcome
+    }
+
+    /// Indices of the enemies still standing and in the fight.
+    pub fn standing(&self) -> Vec<usize> {
+        (0..self.enemies.len())
+            .filter(|&index| !self.enemies[index].is_defeated() && !self.foes[index].fled)
+            .collect()
+    }
+
+    /// `target` if that enemy is still standing, otherwise the first one that is.
+    fn living_target(&self, target: usize) -> Option<usize> {
+        let standing = self.standing();
+        if standing.contains(&target) {
+            Some(target)
+        } else {
+            standing.first().copied()
+        }
+    }
+
+    /// Plays one round: the player's action, then the answer of every enemy
+    /// standing unless the fight is over. Every fighter's status effects tick
+    /// as its turn begins, and a stunned fighter loses the turn. A wound-up
+    /// charge is unleashed whatever the action, and a fight that has ended
+    /// ignores further rounds.
+    pub fn step(
+        &mut self,
+        player: &mut Player,
+        action: CombatAction,
+        rng: &mut impl Rng,
+    ) -> Vec<CombatEvent> {
+        let mut events = Vec::new();
+        if self.outcome.is_some() {
+            return events;
+        }
+
+        let stunned = player.status_effects.has(StatusKind::Stun);
+        for (kind, change) in player.tick_status_effects() {
+            events.push(CombatEvent::StatusTick {
+                target: Fighter::Player,
+                kind,
+                change,
+            });
+        }
+        self.check_defeat(player, &mut events);
+        if self.outcome.is_some() {
+            return events;
+        }
+
+        if stunned {
+            events.push(CombatEvent::Stunned(Fighter::Player));
+        } else if let Some(target) = self.charging.take() {
+            // The blow sweeps through the enemies on either side of the target
+            let targets = self.living_target(target).map_or(Vec::new(), |target| {
+                let standing = self.standing();
+                (target.saturating_sub(1)..=target + 1)
+                    .filter(|index| standing.contains(index))
+                    .collect()
+            });
+            for target in targets {
+                self.player_attack(player, AttackType::Charged, target, rng, &mut events);
+            }
+            *self.attack_counts.entry(AttackType::Charged).or_insert(0) += 1;
+        } else {
+            match action {
+                CombatAction::Main { target } => {
+                    if let Some(target) = self.living_target(target) {
+                        self.player_attack(player, AttackType::Main, target, rng, &mut events);
+                    }
+                    *self.attack_counts.entry(AttackType::Main).or_insert(0) += 1;
+                }
+                CombatAction::Spell if player.skills.contains_key("Magic") => {
+                    for target in self.standing() {
+                        self.player_attack(player, AttackType::Magic, target, rng, &mut events);
+                    }
+                    *self.attack_counts.entry(AttackType::Magic).or_insert(0) += 1;
+                }
+                CombatAction::Spell => events.push(CombatEvent::SpellFailed),
+                CombatAction::Charge { target } => {
+                    info!("Player is preparing a charged attack.");
+                    self.charging = Some(target);
+                    events.push(CombatEvent::ChargeStarted);
+                }
+                CombatAction::Flee if rng.gen_bool(FLEE_CHANCE) => {
+                    info!("Player successfully ran away from combat.");
+                    self.outcome = Some(CombatOutcome::Fled);
+                    events.push(CombatEvent::Fled);
+                    return events;
+                }
+                CombatAction::Flee => events.push(CombatEvent::FleeFailed),
+            }
+        }
+
+        if self.standing().is_empty() {
+            events.push(self.win(player, rng));
+            return events;
+        }
+
+        for index in self.standing() {
+            if self.outcome.is_some() {
+                break;
+            }
+            self.foes[index].energy += self.enemies[index].speed;
+            while self.foes[index].energy >= TURN_ENERGY && self.outcome.is_none() {
+                self.foes[index].energy -= TURN_ENERGY;
+                self.enemy_turn(player, index, rng, &mut events);
+                if self.foes[index].fled || self.enemies[index].is_defeated() {
+                    break;
+                }
+            }
+        }
+
+        // The fight ends once nobody is left standing, won only if someone
+        // was beaten rather than everyone fleeing
+        if self.outcome.is_none() && self.standing().is_empty() {
+            events.push(self.win(player, rng));
+        }
+        events
+    }
+
+    /// One turn of the enemy at `index`, as its behavior picks.
+    fn enemy_turn(
+        &mut self,
+        player: &mut Player,
+        index: usize,
+        rng: &mut impl Rng,
+        events: &mut Vec<CombatEvent>,
+    ) {
+        self.foes[index].blocking = false;
+        let stunned = self.enemies[index].status_effects.has(StatusKind::Stun);
+        for (kind, change) in self.enemies[index].tick_status_effects() {
+            events.push(CombatEvent::StatusTick {
+                target: Fighter::Enemy(index),
+                kind,
+                change,
+            });
+        }
+        if self.enemies[index].is_defeated() {
+            info!("Enemy {} has been defeated", self.enemies[index].name);
+            events.push(CombatEvent::EnemyDefeated(index));
+            return;
+        }
+        if stunned {
+            events.push(CombatEvent::Stunned(Fighter::Enemy(index)));
+            return;
+        }
+
+        let enemy = &self.enemies[index];
+        let mut ability = enemy.behavior.choose(enemy, rng);
+        if ability == Some(Ability::CallForHelp) && self.enemies.len() >= MAX_ENEMIES {
+            ability = None;
+        }
+        debug!("{} takes its turn: {:?}", enemy.name, ability);
+
+        match ability {
+            None | Some(Ability::HeavyHit) | Some(Ability::Poison) | Some(Ability::Rend) => {
+                let hit = if ability == Some(Ability::HeavyHit) {
+                    enemy.heavy_hit(player, rng)
+                } else {
+                    enemy.attack_player(player, rng)
+                };
+                events.push(CombatEvent::EnemyAttacked {
+                    enemy: index,
+                    ability,
+                    hit,
+                });
+                let effect = match (ability, hit) {
+                    (_, Hit::Miss) => None,
+                    (Some(Ability::Poison), _) => Some(POISON),
+                    (Some(Ability::Rend), _) => Some(BLEED),
+                    (Some(Ability::HeavyHit), Hit::Critical(_)) => Some(STUN),
+                    _ => None,
+                };
+                if let Some(effect) = effect {
+                    player.status_effects.apply(effect);
+                    events.push(CombatEvent::StatusApplied {
+                        target: Fighter::Player,
+                        kind: effect.kind,
+                    });
+                }
+                self.check_defeat(player, events);
+            }
+            Some(Ability::Block) => {
+                self.foes[index].blocking = true;
+                events.push(CombatEvent::EnemyBlocked(index));
+            }
+            Some(Ability::Heal) => {
+                let enemy = &mut self.enemies[index];
+                let amount = enemy.heal(enemy.max_health / 4);
+                events.push(CombatEvent::EnemyHealed {
+                    enemy: index,
+                    amount,
+                });
+            }
+            Some(Ability::Flee) => {
+                info!("{} fled from combat", enemy.name);
+                self.foes[index].fled = true;
+                events.push(CombatEvent::EnemyFled(index));
+            }
+            Some(Ability::CallForHelp) => {
+                let mut helper = enemy.clone();
+                helper.health = helper.max_health;
+                self.enemies.push(helper);
+                self.foes.push(Foe::default());
+                events.push(CombatEvent::HelpArrived {
+                    enemy: index,
+                    joined: self.enemies.len() - 1,
+                });
+            }
+        }
+    }
+
+    /// Ends the fight in defeat if the player has no health left.
+    fn check_defeat(&mut self, player: &mut Player, events: &mut Vec<CombatEvent>) {
+        if player.health <= 0 {
+            info!("Player has been defeated");
+            player.exit_combat();
+            self.outcome = Some(CombatOutcome::Defeat);
+            events.push(CombatEvent::Defeat);
+        }
+    }
+
+    fn player_attack(
+        &mut self,
+        player: &Player,
+        attack: AttackType,
+        target: usize,
+        rng: &mut impl Rng,
+        events: &mut Vec<CombatEvent>,
+    ) {
+        let enemy = &mut self.enemies[target];
+        let mut defense = enemy.defense;
+        if self.foes[target].blocking {
+            defense += BLOCK_DEFENSE;
+        }
+        let hit = roll_hit(player_attack(player, attack), defense, rng);
+        enemy.take_damage(hit.damage());
+        debug!(
+            "Player performed a {:?} attack on {}: {:?}",
+            attack, enemy.name, hit
+        );
+        events.push(CombatEvent::PlayerAttacked {
+            target,
+            attack,
+            hit,
+        });
+        if enemy.is_defeated() {
+            info!("Enemy {} has been defeated", enemy.name);
+            events.push(CombatEvent::EnemyDefeated(target));
+            return;
+        }
+
+        let effect = match (attack, hit) {
+            (_, Hit::Miss) => None,
+            (AttackType::Charged, _) => Some(BLEED),
+            (AttackType::Magic, Hit::Critical(_)) => Some(STUN),
+            _ => None,
+        };
+        if let Some(effect) = effect {
+            enemy.status_effects.apply(effect);
+            events.push(CombatEvent::StatusApplied {
+                target: Fighter::Enemy(target),
+                kind: effect.kind,
+            });
+        }
+    }
+
+    /// The enemies beaten in the fight, leaving out those that fled.
+    pub fn defeated(&self) -> impl Iterator<Item = &Enemy> {
+        self.enemies.iter().filter(|enemy| enemy.is_defeated())
+    }
+
+    /// Hands out the skill XP earned by the attacks used, and the XP and loot
+    /// of every enemy defeated. Without any enemy defeated the fight ends
+    /// with `EnemiesFled` instead.
+    fn win(&mut self, player: &mut Player, rng: &mut impl Rng) -> CombatEvent {
+        for (skill_name, xp) in combat_xp_calculation(&self.attack_counts) {
+            if let Some(skill) = player.skills.get_mut(&skill_name) {
+                skill.add_experience(xp as f64);
+            }
+        }
+        let defeated: Vec<&Enemy> = self.defeated().collect();
+        if defeated.is_empty() {
+            self.outcome = Some(CombatOutcome::EnemiesFled);
+            return CombatEvent::EnemiesFled;
+        }
+        let xp = VICTORY_XP * defeated.len() as i32;
+        player.experience += xp;
+
+        let mut loot = HashMap::new();
+        for enemy in defeated {
+            if let Some(loot_table) = self.loot_tables.get(&enemy.loot_table) {
+                for (item_id, quantity) in roll_loot(loot_table, rng) {
+                    *loot.entry(item_id).or_insert(0) += quantity;
+                }
+            }
+        }
+        player.add_loot(&loot);
+
+        self.outcome = Some(CombatOutcome::Victory);
+        CombatEvent::Victory { xp, loot }
+    }
+}
+
+/// Plays a fight in the terminal, turning the player's keys into actions and
+/// the resulting events into text.
 pub fn handle_combat(
     player: &mut Player,
-    mut enemy: Enemy,
+    enemies: Vec<Enemy>,
     loot_tables: &HashMap<String, LootTable>,
-) -> String {
-    info!("Entering combat with {}", enemy.name);
+) -> (CombatOutcome, String) {
+    info!("Entering combat with {}", describe_group(&enemies, "a"));
     let mut rng = rand::thread_rng();
-    let mut charging = false;
-    let mut charge_damage = 0;
+    let mut combat = CombatState::new(enemies, loot_tables);
     let mut combat_action_message = String::new();
 
-    // Introduce attack_counts to keep track of attack types
-    let mut attack_counts: HashMap<AttackType, usize> = HashMap::new();
-
     loop {
         // Clear terminal for better user experience
         print!("\x1B[2J\x1B[1;1H");
@@ -40,11 +504,14 @@ pub fn handle_combat(
 
         // Render the top menu
         println!("(w/a/s/d) move | (status) player status | (quests) view quests");
-        println!("(i) inventory | (m) menu | (q) quit\n");
+        println!("(i) inventory | (e) interact | (m) menu | (q) quit\n");
 
         // Display combat status and previous round actions
         if combat_action_message.is_empty() {
-            println!("\nYou've encountered a {}!", enemy.name);
+            println!(
+                "\nYou've encountered {}!",
+                describe_group(&combat.enemies, "a")
+            );
         } else {
             println!("\n{}", combat_action_message);
         }
@@ -52,254 +519,279 @@ pub fn handle_combat(
         println!();
 
         // Display enemy and player health
-        println!("Enemy: {} (Health: {})", enemy.name, enemy.health);
-        println!("Your health: {}\n", player.health);
-
-        // Handle the charged attack
-        if charging {
-            println!("Press Enter to continue combat...");
-            io::stdin().read_line(&mut String::new()).unwrap();
-
-            charged_attack(player, &mut enemy, charge_damage);
-            charging = false;
-            charge_damage = 0;
-
-            // Increment attack count for Charged attack
-            *attack_counts.entry(AttackType::Charged).or_insert(0) += 1;
-
+        for (index, enemy) in combat.enemies.iter().enumerate() {
             if enemy.is_defeated() {
-                info!("Enemy {} has been defeated", enemy.name);
-
-                // Calculate XP gains based on attack_counts
-                let xp_gains = combat_xp_calculation(&attack_counts);
-                // Add XP to relevant skills
-                for (skill_name, xp) in xp_gains {
-                    if let Some(skill) = player.skills.get_mut(&skill_name) {
-                        skill.add_experience(xp as f64);
-                        // Optionally, display XP gains to the player
-                        // println!("{} gained {} XP.", skill_name, xp);
-                    }
-                }
-                // Clear attack_counts after handling XP
-                attack_counts.clear();
-
-                return handle_enemy_defeat(player, &enemy, loot_tables);
+                println!("Enemy {}: {} (defeated)", index + 1, enemy.name);
+            } else if !combat.standing().contains(&index) {
+                println!("Enemy {}: {} (fled)", index + 1, enemy.name);
+            } else if enemy.status_effects.is_empty() {
+                println!(
+                    "Enemy {}: {} (Health: {})",
+                    index + 1,
+                    enemy.name,
+                    enemy.health
+                );
+            } else {
+                println!(
+                    "Enemy {}: {} (Health: {}) | {}",
+                    index + 1,
+                    enemy.name,
+                    enemy.health,
+                    enemy.status_effects
+                );
             }
-
-            enemy.attack_player(&mut player.health);
-            debug!("{} hit player for {} damage", enemy.name, enemy.attack);
-            combat_action_message = format!(
-                "You performed a charged attack for {} damage!\nThe {} hits you for {} damage!",
-                charge_damage, enemy.name, enemy.attack
+        }
+        if player.status_effects.is_empty() {
+            println!("Your health: {}\n", player.health);
+        } else {
+            println!(
+                "Your health: {} | {}\n",
+                player.health, player.status_effects
             );
+        }
 
-            if player.health <= 0 {
-                info!("Player has been defeated by {}", enemy.name);
-                combat_action_message.push_str("\nNo experience is gained from defeat.");
-                return handle_player_defeat(player, &enemy);
-            }
-
-            continue;
+        let action = if combat.is_charging() {
+            println!("Press Enter to continue combat...");
+            io::stdin().read_line(&mut String::new()).unwrap();
+            // Whatever the action, the round unleashes the charge
+            CombatAction::Main { target: 0 }
         } else {
             println!("Choose (m)ain, (c)harged, (s)pell, (i)tems, or (r)un?");
             let mut action = String::new();
             io::stdin()
                 .read_line(&mut action)
                 .expect("Failed to read line");
-            let action = action.trim();
-
-            match action {
-                "m" => {
-                    main_attack(player, &mut enemy);
-
-                    // Increment attack count for Main attack
-                    *attack_counts.entry(AttackType::Main).or_insert(0) += 1;
-
-                    if enemy.is_defeated() {
-                        // Calculate XP gains
-                        let xp_gains = combat_xp_calculation(&attack_counts);
-                        for (skill_name, xp) in xp_gains {
-                            if let Some(skill) = player.skills.get_mut(&skill_name) {
-                                skill.add_experience(xp as f64);
-                                // Optionally, display XP gains to the player
-                                // println!("{} gained {} XP.", skill_name, xp);
-                            }
-                        }
-                        attack_counts.clear();
-
-                        return handle_enemy_defeat(player, &enemy, loot_tables);
-                    }
-                    combat_action_message = format!(
-                        "You hit the {} for {} damage!\nThe {} hits you for {} damage!",
-                        enemy.name, 10, enemy.name, enemy.attack
-                    );
-                }
-                "c" => {
-                    charging = true;
-                    charge_damage = 10 * 3;
-                    info!("Player is preparing a charged attack.");
-                    combat_action_message = format!(
-                        "You are preparing a charged attack...\nThe {} hits you for {} damage!",
-                        enemy.name, enemy.attack
-                    );
-
-                    enemy.attack_player(&mut player.health);
-                    debug!("{} hit player for {} damage", enemy.name, enemy.attack);
-
-                    if player.health <= 0 {
-                        info!("Player has been defeated by {}", enemy.name);
-                        combat_action_message.push_str("\nNo experience is gained from defeat.");
-                        return handle_player_defeat(player, &enemy);
-                    }
 
-                    continue;
-                }
-                "s" => {
-                    spell_attack(player, &mut enemy);
-
-                    // Increment attack count for Magic attack
-                    *attack_counts.entry(AttackType::Magic).or_insert(0) += 1;
-
-                    if enemy.is_defeated() {
-                        // Calculate XP gains
-                        let xp_gains = combat_xp_calculation(&attack_counts);
-                        for (skill_name, xp) in xp_gains {
-                            if let Some(skill) = player.skills.get_mut(&skill_name) {
-                                skill.add_experience(xp as f64);
-                                // Optionally, display XP gains to the player
-                                // println!("{} gained {} XP.", skill_name, xp);
-                            }
-                        }
-                        attack_counts.clear();
-
-                        return handle_enemy_defeat(player, &enemy, loot_tables);
+            match action.trim() {
+                "m" | "c" => match choose_target(&combat) {
+                    Some(target) if action.trim() == "m" => CombatAction::Main { target },
+                    Some(target) => CombatAction::Charge { target },
+                    None => {
+                        combat_action_message = "There is no such enemy.".to_string();
+                        continue;
                     }
-                    combat_action_message = format!(
-                        "You cast a spell on the {} for {} damage!\nThe {} hits you for {} damage!",
-                        enemy.name, 15, enemy.name, enemy.attack
-                    );
-                }
+                },
+                "s" => CombatAction::Spell,
+                "r" => CombatAction::Flee,
                 "i" => {
                     display_and_handle_inventory(player, None);
                     continue;
                 }
-                "r" => {
-                    if rng.gen_bool(0.5) {
-                        info!("Player successfully ran away from combat.");
-                        println!("\nYou successfully ran away!");
-                        return "Ran away from combat.".to_string();
-                    } else {
-                        combat_action_message = format!(
-                            "You attempted to run away but failed!\nThe {} hits you for {} damage!",
-                            enemy.name, enemy.attack
-                        );
-
-                        enemy.attack_player(&mut player.health);
-                        debug!("{} hit player for {} damage", enemy.name, enemy.attack);
-
-                        if player.health <= 0 {
-                            info!("Player has been defeated by {}", enemy.name);
-                            combat_action_message.push_str("\nNo experience is gained from defeat.");
-                            return handle_player_defeat(player, &enemy);
-                        }
-
-                        continue;
-                    }
-                }
                 _ => {
-                    println!(
-                        "\nInvalid command. Please enter 'm', 'c', 's', 'i', or 'r'."
-                    );
+                    combat_action_message =
+                        "Invalid command. Please enter 'm', 'c', 's', 'i', or 'r'.".to_string();
                     continue;
                 }
             }
+        };
 
-            // Enemy attacks player after player's action
-            enemy.attack_player(&mut player.health);
-            debug!("{} hit player for {} damage", enemy.name, enemy.attack);
+        let events = combat.step(player, action, &mut rng);
+        combat_action_message = events
+            .iter()
+            .map(|event| describe_event(event, player, &combat.enemies))
+            .collect::<Vec<_>>()
+            .join("\n");
 
-            if player.health <= 0 {
-                info!("Player has been defeated by {}", enemy.name);
-                combat_action_message.push_str("\nNo experience is gained from defeat.");
-                return handle_player_defeat(player, &enemy);
+        match combat.outcome() {
+            None => {}
+            Some(CombatOutcome::Victory) => {
+                let summary = events.iter().find_map(|event| match event {
+                    CombatEvent::Victory { xp, loot } => {
+                        Some(show_victory(&combat.enemies, *xp, loot))
+                    }
+                    _ => None,
+                });
+                return (CombatOutcome::Victory, summary.unwrap_or_default());
+            }
+            Some(CombatOutcome::Fled) => {
+                println!("\n{}", combat_action_message);
+                return (CombatOutcome::Fled, "Ran away from combat.".to_string());
+            }
+            Some(CombatOutcome::EnemiesFled) => {
+                println!("\n{}", combat_action_message);
+                return (
+                    CombatOutcome::EnemiesFled,
+                    "Your enemies got away.".to_string(),
+                );
+            }
+            Some(CombatOutcome::Defeat) => {
+                println!("{}", combat_action_message);
+                return (CombatOutcome::Defeat, "You were defeated...".to_string());
             }
         }
     }
 }
 
-fn main_attack(player: &mut Player, enemy: &mut Enemy) {
-    let mut damage = 10; // Base damage for main attack
-
-    // Increase damage if a weapon is equipped
-    if let Some(weapon) = &player.equipped_weapon {
-        damage += weapon.attack_bonus.unwrap_or(0);
-        println!(
-            "\nYou attack with your {} for {} damage!",
-            weapon.name, damage
-        );
-    } else {
-        println!("\nYou attack with your fists for {} damage!", damage);
+/// Asks which enemy to strike when more than one is standing. `None` if the
+/// answer names no enemy standing.
+fn choose_target(combat: &CombatState) -> Option<usize> {
+    let standing = combat.standing();
+    if standing.len() <= 1 {
+        return standing.first().copied();
     }
+    let choices: Vec<String> = standing
+        .iter()
+        .map(|index| (index + 1).to_string())
+        .collect();
+    println!("Which enemy? ({})", choices.join("/"));
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .expect("Failed to read line");
+    let target = answer.trim().parse::<usize>().ok()?.checked_sub(1)?;
+    standing.contains(&target).then_some(target)
+}
 
-    enemy.take_damage(damage);
+/// The enemies as a phrase, e.g. "a Goblin" or "the Bandit and the Wolf".
+fn describe_group(enemies: &[Enemy], article: &str) -> String {
+    let names: Vec<String> = enemies
+        .iter()
+        .map(|enemy| format!("{} {}", article, enemy.name))
+        .collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        Some((last, _)) => last.clone(),
+        None => String::new(),
+    }
 }
 
-fn spell_attack(player: &mut Player, enemy: &mut Enemy) {
-    if player.skills.get("Magic").is_some() {
-        let damage = 15; // Example magic attack damage
-        enemy.take_damage(damage);
-        println!(
-            "\nYou cast a spell on the {} for {} damage!",
-            enemy.name, damage
-        );
+/// "You have defeated the Goblin!", or word that the enemies got away.
+fn describe_victory(enemies: &[Enemy]) -> String {
+    let defeated: Vec<Enemy> = enemies
+        .iter()
+        .filter(|enemy| enemy.is_defeated())
+        .cloned()
+        .collect();
+    if defeated.is_empty() {
+        "Your enemies have fled.".to_string()
     } else {
-        println!("\nYou don't have enough magic ability to cast a spell!");
+        format!("You have defeated {}!", describe_group(&defeated, "the"))
     }
 }
 
-fn charged_attack(_player: &mut Player, enemy: &mut Enemy, charge_damage: i32) {
-    // Prefixed unused variable with underscore
-    enemy.take_damage(charge_damage);
-    debug!(
-        "Player performed a charged attack for {} damage!",
-        charge_damage
-    );
-    println!(
-        "\nYou performed a charged attack for {} damage!",
-        charge_damage
-    );
+/// One line telling the player what happened.
+fn describe_event(event: &CombatEvent, player: &Player, enemies: &[Enemy]) -> String {
+    match event {
+        CombatEvent::PlayerAttacked {
+            target,
+            attack,
+            hit,
+        } => {
+            let name = &enemies[*target].name;
+            let attack = match attack {
+                AttackType::Main => {
+                    let weapon = player
+                        .equipped_weapon
+                        .as_ref()
+                        .map_or("your fists".to_string(), |weapon| {
+                            format!("your {}", weapon.name)
+                        });
+                    format!("You attack the {} with {}", name, weapon)
+                }
+                AttackType::Charged => format!("You unleash your charged attack on the {}", name),
+                AttackType::Magic => format!("You cast a spell on the {}", name),
+            };
+            describe_hit(*hit, &attack)
+        }
+        CombatEvent::EnemyDefeated(target) => format!("The {} falls!", enemies[*target].name),
+        CombatEvent::ChargeStarted => "You are preparing a charged attack...".to_string(),
+        CombatEvent::SpellFailed => {
+            "You don't have enough magic ability to cast a spell!".to_string()
+        }
+        CombatEvent::EnemyAttacked {
+            enemy,
+            ability,
+            hit,
+        } => {
+            let name = &enemies[*enemy].name;
+            let attack = match ability {
+                Some(Ability::HeavyHit) => format!("The {} swings a heavy blow at you", name),
+                Some(Ability::Poison) => format!("The {} lands a poisoned blow", name),
+                Some(Ability::Rend) => format!("The {} tears into you", name),
+                _ => format!("The {} attacks you", name),
+            };
+            describe_hit(*hit, &attack)
+        }
+        CombatEvent::EnemyBlocked(enemy) => {
+            format!("The {} raises its guard.", enemies[*enemy].name)
+        }
+        CombatEvent::EnemyHealed { enemy, amount } => {
+            format!("The {} recovers {} health.", enemies[*enemy].name, amount)
+        }
+        CombatEvent::EnemyFled(enemy) => format!("The {} runs away!", enemies[*enemy].name),
+        CombatEvent::HelpArrived { enemy, joined } => format!(
+            "The {} calls for help, and another {} joins the fight!",
+            enemies[*enemy].name, enemies[*joined].name
+        ),
+        CombatEvent::StatusApplied { target, kind } => {
+            let state = kind.to_string().to_lowercase();
+            match target {
+                Fighter::Player => format!("You are {}!", state),
+                Fighter::Enemy(enemy) => format!("The {} is {}!", enemies[*enemy].name, state),
+            }
+        }
+        CombatEvent::StatusTick {
+            target,
+            kind,
+            change,
+        } => {
+            let target = match target {
+                Fighter::Player => "you".to_string(),
+                Fighter::Enemy(enemy) => format!("the {}", enemies[*enemy].name),
+            };
+            describe_tick(*kind, *change, &target)
+        }
+        CombatEvent::Stunned(Fighter::Player) => "You are stunned and can't act!".to_string(),
+        CombatEvent::Stunned(Fighter::Enemy(enemy)) => {
+            format!("The {} is stunned and can't act!", enemies[*enemy].name)
+        }
+        CombatEvent::FleeFailed => "You attempted to run away but failed!".to_string(),
+        CombatEvent::Fled => "You successfully ran away!".to_string(),
+        CombatEvent::Victory { .. } => describe_victory(enemies),
+        CombatEvent::EnemiesFled => "Nobody is left to fight.".to_string(),
+        CombatEvent::Defeat => {
+            format!(
+                "You have been defeated by {}...",
+                describe_group(enemies, "the")
+            )
+        }
+    }
 }
 
-fn handle_enemy_defeat(
-    player: &mut Player,
-    enemy: &Enemy,
-    loot_tables: &HashMap<String, LootTable>,
-) -> String {
+/// One line telling how `attack` went, e.g. "You attack the Goblin with your
+/// fists for 7 damage!" or "The Goblin attacks you, but it misses!".
+fn describe_hit(hit: Hit, attack: &str) -> String {
+    match hit {
+        Hit::Miss => format!("{}, but it misses!", attack),
+        Hit::Normal(damage) => format!("{} for {} damage!", attack, damage),
+        Hit::Critical(damage) => format!("Critical hit! {} for {} damage!", attack, damage),
+    }
+}
+
+/// Shows what the won fight brought in and waits for the player.
+fn show_victory(enemies: &[Enemy], xp_gain: i32, loot: &HashMap<u32, u32>) -> String {
+    let defeated: Vec<Enemy> = enemies
+        .iter()
+        .filter(|enemy| enemy.is_defeated())
+        .cloned()
+        .collect();
     // Clear the terminal for better readability of combat results
     print!("\x1B[2J\x1B[1;1H");
     io::stdout().flush().unwrap();
 
     // Display the defeat information
-    println!("You have defeated the {}!", enemy.name);
-    let xp_gain = 10;
-    player.experience += xp_gain;
-
-    let mut loot_message = String::new();
-    if let Some(loot_table) = loot_tables.get(&enemy.loot_table) {
-        let loot = calculate_loot(loot_table);
-        player.add_loot(&loot);
-
-        for (item_id, quantity) in loot {
-            if let Some(item) = create_items().get(&item_id) {
-                loot_message.push_str(&format!("({}) {}, ", quantity, item.name));
-            }
-        }
+    println!("{}", describe_victory(enemies));
 
-        if !loot_message.is_empty() {
-            loot_message.pop();
-            loot_message.pop();
-        }
-    }
+    let items = create_items();
+    let loot_message = loot
+        .iter()
+        .filter_map(|(item_id, quantity)| {
+            items
+                .get(item_id)
+                .map(|item| format!("({}) {}", quantity, item.name))
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
 
     // Display combat results and loot
     println!("\n[Combat Results]");
@@ -314,27 +806,471 @@ fn handle_enemy_defeat(
     io::stdin().read_line(&mut String::new()).unwrap();
 
     format!(
-        "Defeated a {} | +{} XP | Looted: {}",
-        enemy.name, xp_gain, loot_message
+        "Defeated {} | +{} XP | Looted: {}",
+        describe_group(&defeated, "a"),
+        xp_gain,
+        loot_message
     )
 }
 
-fn handle_player_defeat(player: &mut Player, enemy: &Enemy) -> String {
-    println!("You have been defeated by the {}...", enemy.name);
-    player.in_combat = false;
-    "You were defeated...".to_string()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::Behavior;
+    use crate::items::create_loot_tables;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
-fn check_combat_end(
-    player: &mut Player,
-    enemy: &mut Enemy,
-    loot_tables: &HashMap<String, LootTable>,
-) -> Option<String> {
-    if enemy.is_defeated() {
-        Some(handle_enemy_defeat(player, enemy, loot_tables))
-    } else if player.health <= 0 {
-        Some(handle_player_defeat(player, enemy))
-    } else {
-        None
+    const MAIN: CombatAction = CombatAction::Main { target: 0 };
+
+    /// Plays `action` every round until the fight ends, returning every event.
+    fn play_out(
+        combat: &mut CombatState,
+        player: &mut Player,
+        action: CombatAction,
+        rng: &mut StdRng,
+    ) -> Vec<CombatEvent> {
+        let mut events = Vec::new();
+        for _ in 0..1000 {
+            if combat.outcome().is_some() {
+                break;
+            }
+            events.extend(combat.step(player, action, rng));
+        }
+        events
+    }
+
+    #[test]
+    fn a_strong_player_beats_a_goblin_and_takes_the_spoils() {
+        let loot_tables = create_loot_tables();
+        let mut player = Player::new();
+        player.skills.get_mut("Attack").unwrap().level = 60;
+        player.skills.get_mut("Strength").unwrap().level = 60;
+        let goblin = Enemy::new("Goblin", 30, 5, 1, "common");
+        let mut combat = CombatState::new(vec![goblin], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(22);
+
+        let events = play_out(&mut combat, &mut player, MAIN, &mut rng);
+        assert_eq!(combat.outcome(), Some(CombatOutcome::Victory));
+        assert!(combat.standing().is_empty());
+        assert_eq!(player.experience, VICTORY_XP);
+        assert!(player.skills["Attack"].experience > 0.0);
+
+        let Some(CombatEvent::Victory { xp, loot }) = events.last() else {
+            panic!("the fight should end in victory: {:?}", events);
+        };
+        assert_eq!(*xp, VICTORY_XP);
+        for (item_id, quantity) in loot {
+            assert!(player.inventory[item_id] >= *quantity);
+        }
+
+        // The enemy strikes back after every round but the last
+        let enemy_turns = events
+            .iter()
+            .filter(|event| matches!(event, CombatEvent::EnemyAttacked { .. }))
+            .count();
+        let player_turns = events
+            .iter()
+            .filter(|event| matches!(event, CombatEvent::PlayerAttacked { .. }))
+            .count();
+        assert_eq!(enemy_turns, player_turns - 1);
+
+        // Nothing more happens once the fight is over
+        assert!(combat.step(&mut player, MAIN, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn a_charge_is_unleashed_the_next_round_on_the_target_and_its_neighbours() {
+        let loot_tables = create_loot_tables();
+        let mut player = Player::new();
+        let troll = Enemy::new("Troll", 500, 1, 0, "rare");
+        let mut combat = CombatState::new(vec![troll; 4], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let events = combat.step(&mut player, CombatAction::Charge { target: 1 }, &mut rng);
+        assert_eq!(events[0], CombatEvent::ChargeStarted);
+        assert_eq!(events.len(), 5, "every troll answers: {:?}", events);
+        assert!(combat.is_charging());
+
+        let events = combat.step(&mut player, CombatAction::Flee, &mut rng);
+        let targets: Vec<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                CombatEvent::PlayerAttacked {
+                    target,
+                    attack: AttackType::Charged,
+                    ..
+                } => Some(*target),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(targets, vec![0, 1, 2]);
+        assert!(!combat.is_charging());
+        assert_eq!(combat.outcome(), None);
+    }
+
+    #[test]
+    fn spells_strike_every_enemy_standing() {
+        let loot_tables = create_loot_tables();
+        let mut player = Player::new();
+        let goblin = Enemy::new("Goblin", 500, 1, 0, "common");
+        let mut combat = CombatState::new(vec![goblin; 3], &loot_tables);
+        combat.enemies[1].health = 0;
+        let mut rng = StdRng::seed_from_u64(2);
+
+        let events = combat.step(&mut player, CombatAction::Spell, &mut rng);
+        let targets: Vec<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                CombatEvent::PlayerAttacked { target, .. } => Some(*target),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(targets, vec![0, 2]);
+    }
+
+    #[test]
+    fn fallen_targets_give_way_to_the_next_enemy() {
+        let loot_tables = create_loot_tables();
+        let mut player = Player::new();
+        let goblin = Enemy::new("Goblin", 500, 1, 0, "common");
+        let mut combat = CombatState::new(vec![goblin; 2], &loot_tables);
+        combat.enemies[0].health = 0;
+        let mut rng = StdRng::seed_from_u64(4);
+
+        let events = combat.step(&mut player, CombatAction::Main { target: 0 }, &mut rng);
+        assert!(matches!(
+            events[0],
+            CombatEvent::PlayerAttacked { target: 1, .. }
+        ));
+        assert!(matches!(
+            events[1],
+            CombatEvent::EnemyAttacked { enemy: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn a_won_fight_pays_out_for_every_enemy() {
+        let loot_tables = create_loot_tables();
+        let mut player = Player::new();
+        player.skills.get_mut("Attack").unwrap().level = 60;
+        player.skills.get_mut("Strength").unwrap().level = 60;
+        let pack = vec![
+            Enemy::new("Goblin", 10, 1, 0, "common"),
+            Enemy::new("Bandit", 10, 1, 0, "common_food"),
+            Enemy::new("Wolf", 10, 1, 0, "uncommon"),
+        ];
+        let mut combat = CombatState::new(pack, &loot_tables);
+        let mut rng = StdRng::seed_from_u64(23);
+
+        let events = play_out(&mut combat, &mut player, MAIN, &mut rng);
+        assert_eq!(combat.outcome(), Some(CombatOutcome::Victory));
+        let defeated = events
+            .iter()
+            .filter(|event| matches!(event, CombatEvent::EnemyDefeated(_)))
+            .count();
+        assert_eq!(defeated, 3);
+        assert!(matches!(
+            events.last(),
+            Some(CombatEvent::Victory { xp, .. }) if *xp == 3 * VICTORY_XP
+        ));
+        assert_eq!(player.experience, 3 * VICTORY_XP);
+    }
+
+    #[test]
+    fn losing_gives_no_experience() {
+        let loot_tables = create_loot_tables();
+        let mut player = Player::new();
+        player.health = 1;
+        player.in_combat = true;
+        let troll = Enemy::new("Troll", 500, 1000, 0, "rare");
+        let mut combat = CombatState::new(vec![troll], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let events = play_out(&mut combat, &mut player, MAIN, &mut rng);
+        assert_eq!(combat.outcome(), Some(CombatOutcome::Defeat));
+        assert_eq!(events.last(), Some(&CombatEvent::Defeat));
+        assert_eq!(player.health, 0);
+        assert!(!player.in_combat);
+        assert_eq!(player.experience, 0);
+        assert_eq!(player.skills["Attack"].experience, 0.0);
+    }
+
+    #[test]
+    fn running_away_ends_the_fight() {
+        let loot_tables = create_loot_tables();
+        let mut player = Player::new();
+        let goblin = Enemy::new("Goblin", 30, 1, 0, "common");
+        let mut combat = CombatState::new(vec![goblin], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let events = play_out(&mut combat, &mut player, CombatAction::Flee, &mut rng);
+        assert_eq!(combat.outcome(), Some(CombatOutcome::Fled));
+        assert_eq!(events.last(), Some(&CombatEvent::Fled));
+        assert_eq!(combat.enemies[0].health, 30);
+    }
+
+    #[test]
+    fn the_same_seed_fights_the_same_way() {
+        let loot_tables = create_loot_tables();
+        let fight = |seed| {
+            let mut player = Player::new();
+            let orcs = vec![Enemy::new("Orc", 50, 10, 4, "uncommon"); 2];
+            let mut combat = CombatState::new(orcs, &loot_tables);
+            let mut rng = StdRng::seed_from_u64(seed);
+            play_out(&mut combat, &mut player, MAIN, &mut rng)
+        };
+        assert_eq!(fight(9), fight(9));
+    }
+
+    /// Plays `rounds` rounds of `action`, returning every event.
+    fn play_rounds(
+        combat: &mut CombatState,
+        player: &mut Player,
+        action: CombatAction,
+        rounds: usize,
+        rng: &mut StdRng,
+    ) -> Vec<CombatEvent> {
+        (0..rounds)
+            .flat_map(|_| combat.step(player, action, rng))
+            .collect()
+    }
+
+    /// A player who can take a beating and knows no magic, so casting a spell
+    /// lets the enemies act undisturbed.
+    fn sturdy_player() -> Player {
+        let mut player = Player::new();
+        player.health = 100_000;
+        player.skills.remove("Magic");
+        player
+    }
+
+    const IDLE: CombatAction = CombatAction::Spell;
+
+    #[test]
+    fn faster_enemies_act_more_often() {
+        let loot_tables = create_loot_tables();
+        let mut player = sturdy_player();
+        let wolf = Enemy {
+            speed: 20,
+            ..Enemy::new("Wolf", 100_000, 1, 0, "uncommon")
+        };
+        let troll = Enemy {
+            speed: 5,
+            ..Enemy::new("Troll", 100_000, 1, 0, "rare")
+        };
+        let mut combat = CombatState::new(vec![wolf, troll], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(24);
+
+        let events = play_rounds(&mut combat, &mut player, MAIN, 10, &mut rng);
+        let turns = |index| {
+            events
+                .iter()
+                .filter(|event| {
+                    matches!(event, CombatEvent::EnemyAttacked { enemy, .. } if *enemy == index)
+                })
+                .count()
+        };
+        assert_eq!(turns(0), 20);
+        assert_eq!(turns(1), 5);
+    }
+
+    #[test]
+    fn blocking_enemies_are_harder_to_hit() {
+        let loot_tables = create_loot_tables();
+        let mut player = sturdy_player();
+        let skeleton = Enemy {
+            abilities: vec![Ability::Block],
+            behavior: Behavior::Guardian,
+            ..Enemy::new("Skeleton", 100_000, 1, 0, "uncommon")
+        };
+        let mut combat = CombatState::new(vec![skeleton], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(6);
+
+        let events = play_rounds(&mut combat, &mut player, MAIN, 50, &mut rng);
+        assert!(events.contains(&CombatEvent::EnemyBlocked(0)));
+        // The guard stays up until the skeleton's next turn
+        let blocked_last = events
+            .iter()
+            .rev()
+            .find(|event| {
+                matches!(
+                    event,
+                    CombatEvent::EnemyBlocked(_) | CombatEvent::EnemyAttacked { .. }
+                )
+            })
+            .is_some_and(|event| *event == CombatEvent::EnemyBlocked(0));
+        assert_eq!(combat.foes[0].blocking, blocked_last);
+    }
+
+    #[test]
+    fn hurt_enemies_heal_call_for_help_and_flee() {
+        let loot_tables = create_loot_tables();
+        let mut player = sturdy_player();
+        let mut rng = StdRng::seed_from_u64(8);
+
+        let troll = Enemy {
+            health: 100,
+            abilities: vec![Ability::Heal],
+            behavior: Behavior::Brute,
+            ..Enemy::new("Troll", 400, 1, 0, "rare")
+        };
+        let mut combat = CombatState::new(vec![troll], &loot_tables);
+        let events = play_rounds(&mut combat, &mut player, IDLE, 30, &mut rng);
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, CombatEvent::EnemyHealed { amount: 100, .. })));
+        // Past half health the troll stops healing
+        assert_eq!(combat.enemies[0].health, 300);
+
+        let wolf = Enemy {
+            health: 40,
+            abilities: vec![Ability::CallForHelp],
+            behavior: Behavior::Pack,
+            ..Enemy::new("Wolf", 100, 1, 0, "uncommon")
+        };
+        let mut combat = CombatState::new(vec![wolf], &loot_tables);
+        play_rounds(&mut combat, &mut player, IDLE, 40, &mut rng);
+        assert_eq!(combat.enemies.len(), MAX_ENEMIES);
+        assert_eq!(combat.enemies[1].health, 100);
+
+        let goblin = Enemy {
+            health: 1,
+            abilities: vec![Ability::Flee],
+            behavior: Behavior::Pack,
+            ..Enemy::new("Goblin", 100, 1, 0, "common")
+        };
+        let mut combat = CombatState::new(vec![goblin], &loot_tables);
+        let events = combat.step(&mut player, IDLE, &mut rng);
+        assert!(events.contains(&CombatEvent::EnemyFled(0)));
+        // Nothing was beaten, so nothing was won
+        assert_eq!(events.last(), Some(&CombatEvent::EnemiesFled));
+        assert_eq!(combat.outcome(), Some(CombatOutcome::EnemiesFled));
+        assert_eq!(player.experience, 0);
+    }
+
+    #[test]
+    fn poison_keeps_hurting_for_a_few_rounds() {
+        let loot_tables = create_loot_tables();
+        let mut player = sturdy_player();
+        let bandit = Enemy {
+            accuracy: 1000,
+            abilities: vec![Ability::Poison],
+            ..Enemy::new("Bandit", 100_000, 1, 0, "common_food")
+        };
+        let mut combat = CombatState::new(vec![bandit], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(10);
+
+        let poisoned = CombatEvent::StatusApplied {
+            target: Fighter::Player,
+            kind: StatusKind::Poison,
+        };
+        while !combat.step(&mut player, IDLE, &mut rng).contains(&poisoned) {}
+        assert!(player.status_effects.has(StatusKind::Poison));
+
+        // The bandit stops poisoning, and the poison wears off
+        combat.enemies[0].abilities.clear();
+        let events = play_rounds(&mut combat, &mut player, IDLE, 5, &mut rng);
+        let ticks = events
+            .iter()
+            .filter(|event| {
+                **event
+                    == CombatEvent::StatusTick {
+                        target: Fighter::Player,
+                        kind: StatusKind::Poison,
+                        change: -POISON.potency,
+                    }
+            })
+            .count();
+        assert_eq!(ticks as u32, POISON.turns);
+        assert!(!player.status_effects.has(StatusKind::Poison));
+    }
+
+    #[test]
+    fn stunned_fighters_lose_their_turn() {
+        let loot_tables = create_loot_tables();
+        let mut player = sturdy_player();
+        let goblin = Enemy::new("Goblin", 100_000, 1, 0, "common");
+        let mut combat = CombatState::new(vec![goblin], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(12);
+
+        player.status_effects.apply(STUN);
+        combat.enemies[0].status_effects.apply(STUN);
+        let events = combat.step(&mut player, MAIN, &mut rng);
+        assert_eq!(
+            events,
+            vec![
+                CombatEvent::Stunned(Fighter::Player),
+                CombatEvent::Stunned(Fighter::Enemy(0)),
+            ]
+        );
+
+        // A stun lasts a single turn
+        let events = combat.step(&mut player, MAIN, &mut rng);
+        assert!(matches!(events[0], CombatEvent::PlayerAttacked { .. }));
+        assert!(matches!(events[1], CombatEvent::EnemyAttacked { .. }));
+    }
+
+    #[test]
+    fn charged_blows_leave_enemies_bleeding() {
+        let loot_tables = create_loot_tables();
+        let mut player = sturdy_player();
+        player.skills.get_mut("Attack").unwrap().level = 99;
+        let troll = Enemy::new("Troll", 100_000, 1, 0, "rare");
+        let mut combat = CombatState::new(vec![troll], &loot_tables);
+        let mut rng = StdRng::seed_from_u64(13);
+
+        let bleeding = CombatEvent::StatusApplied {
+            target: Fighter::Enemy(0),
+            kind: StatusKind::Bleed,
+        };
+        let charge = CombatAction::Charge { target: 0 };
+        while !play_rounds(&mut combat, &mut player, charge, 2, &mut rng).contains(&bleeding) {}
+
+        let health = combat.enemies[0].health;
+        let events = combat.step(&mut player, IDLE, &mut rng);
+        assert!(events.contains(&CombatEvent::StatusTick {
+            target: Fighter::Enemy(0),
+            kind: StatusKind::Bleed,
+            change: -BLEED.potency,
+        }));
+        assert_eq!(combat.enemies[0].health, health - BLEED.potency);
+    }
+
+    #[test]
+    fn enemies_that_bleed_out_fall_once() {
+        let loot_tables = create_loot_tables();
+        let mut player = sturdy_player();
+        let wolf = Enemy {
+            health: 1,
+            speed: 20,
+            ..Enemy::new("Wolf", 35, 1, 0, "uncommon")
+        };
+        let mut combat = CombatState::new(vec![wolf], &loot_tables);
+        combat.enemies[0].status_effects.apply(BLEED);
+        let mut rng = StdRng::seed_from_u64(25);
+
+        let events = combat.step(&mut player, IDLE, &mut rng);
+        let falls = events
+            .iter()
+            .filter(|event| matches!(event, CombatEvent::EnemyDefeated(_)))
+            .count();
+        assert_eq!(falls, 1, "{:?}", events);
+        assert!(matches!(
+            events.last(),
+            Some(CombatEvent::Victory { xp, .. }) if *xp == VICTORY_XP
+        ));
+    }
+
+    #[test]
+    fn groups_are_named_in_a_sentence() {
+        let goblin = Enemy::new("Goblin", 30, 5, 1, "common");
+        let wolf = Enemy::new("Wolf", 35, 7, 2, "uncommon");
+        let group = [goblin.clone(), goblin, wolf];
+        assert_eq!(describe_group(&group[..1], "a"), "a Goblin");
+        assert_eq!(
+            describe_group(&group, "the"),
+            "the Goblin, the Goblin and the Wolf"
+        );
     }
 }
@@ -0,0 +1,222 @@
+use crate::enemy::Enemy;
+use crate::player::Player;
+use crate::skill::AttackType;
+use crate::status::StatusKind;
+use rand::Rng;
+
+/// Chance that a blow that lands is a critical hit.
+pub const CRIT_CHANCE: f64 = 0.1;
+
+/// Hit chances never go past these, however far apart the stats are.
+const MIN_HIT_CHANCE: f64 = 0.2;
+const MAX_HIT_CHANCE: f64 = 0.95;
+
+/// What an attack can do before the target's defense is taken into account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attack {
+    pub accuracy: i32,
+    pub max_hit: i32,
+}
+
+/// How a rolled attack turned out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hit {
+    Miss,
+    Normal(i32),
+    Critical(i32),
+}
+
+impl Attack {
+    /// A clumsier blow that hits twice as hard.
+    pub fn heavy(self) -> Attack {
+        Attack {
+            accuracy: self.accuracy - 5,
+            max_hit: self.max_hit * 2,
+        }
+    }
+}
+
+impl Hit {
+    pub fn damage(self) -> i32 {
+        match self {
+            Hit::Miss => 0,
+            Hit::Normal(damage) | Hit::Critical(damage) => damage,
+        }
+    }
+}
+
+fn skill_level(player: &Player, skill: &str) -> i32 {
+    player.skills.get(skill).map_or(1, |skill| skill.level)
+}
+
+/// The player's attack of the given type. Melee attacks grow with Attack
+/// (accuracy), Strength (damage), the weapon's bonus and feeling mighty;
+/// spells only with Magic.
+pub fn player_attack(player: &Player, attack_type: AttackType) -> Attack {
+    let weapon_bonus = player
+        .equipped_weapon
+        .as_ref()
+        .and_then(|weapon| weapon.attack_bonus)
+        .unwrap_or(0);
+    let accuracy = skill_level(player, "Attack") + weapon_bonus;
+    let might = player.status_effects.potency(StatusKind::Might);
+    let max_hit = 10 + skill_level(player, "Strength") / 2 + weapon_bonus + might;
+    match attack_type {
+        AttackType::Main => Attack { accuracy, max_hit },
+        // Winding up takes a turn, so the blow is surer and three times as hard
+        AttackType::Charged => Attack {
+            accuracy: accuracy + 10,
+            max_hit: max_hit * 3,
+        },
+        AttackType::Magic => {
+            let magic = skill_level(player, "Magic");
+            Attack {
+                accuracy: magic * 2,
+                max_hit: 15 + magic / 2,
+            }
+        }
+    }
+}
+
+/// How well the player's armor, and any guard they are under, wards off
+/// blows.
+pub fn player_defense(player: &Player) -> i32 {
+    let armor = player
+        .equipped_armor
+        .as_ref()
+        .and_then(|armor| armor.defense_bonus)
+        .unwrap_or(0);
+    armor + player.status_effects.potency(StatusKind::Guard)
+}
+
+pub fn enemy_attack(enemy: &Enemy) -> Attack {
+    Attack {
+        accuracy: enemy.accuracy,
+        max_hit: enemy.attack,
+    }
+}
+
+/// Chance that an attack with `accuracy` lands on a target with `defense`.
+pub fn hit_chance(accuracy: i32, defense: i32) -> f64 {
+    (0.75 + 0.02 * (accuracy - defense) as f64).clamp(MIN_HIT_CHANCE, MAX_HIT_CHANCE)
+}
+
+/// Rolls `attack` against a target with `defense`. A hit deals between 1 and
+/// the attack's max hit, less half the defense but never below 1; a critical
+/// hit deals one and a half times the max hit and goes through defense.
+pub fn roll_hit<R: Rng>(attack: Attack, defense: i32, rng: &mut R) -> Hit {
+    if !rng.gen_bool(hit_chance(attack.accuracy, defense)) {
+        return Hit::Miss;
+    }
+    let max_hit = attack.max_hit.max(1);
+    if rng.gen_bool(CRIT_CHANCE) {
+        return Hit::Critical(max_hit * 3 / 2);
+    }
+    let damage = rng.gen_range(1..=max_hit) - defense / 2;
+    Hit::Normal(damage.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::create_items;
+    use crate::status::StatusEffect;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Average damage per attack over many seeded rolls.
+    fn average_damage(attack: Attack, defense: i32) -> f64 {
+        let mut rng = StdRng::seed_from_u64(21);
+        let total: i32 = (0..10_000)
+            .map(|_| roll_hit(attack, defense, &mut rng).damage())
+            .sum();
+        total as f64 / 10_000.0
+    }
+
+    #[test]
+    fn hit_chance_follows_accuracy_and_defense() {
+        assert_eq!(hit_chance(10, 10), 0.75);
+        assert!(hit_chance(20, 10) > hit_chance(10, 10));
+        assert!(hit_chance(10, 20) < hit_chance(10, 10));
+        assert_eq!(hit_chance(1000, 0), MAX_HIT_CHANCE);
+        assert_eq!(hit_chance(0, 1000), MIN_HIT_CHANCE);
+    }
+
+    #[test]
+    fn rolls_stay_within_the_formula() {
+        let attack = Attack {
+            accuracy: 10,
+            max_hit: 12,
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        let (mut misses, mut crits) = (0, 0);
+        for _ in 0..1000 {
+            match roll_hit(attack, 4, &mut rng) {
+                Hit::Miss => misses += 1,
+                Hit::Normal(damage) => assert!((1..=10).contains(&damage)),
+                Hit::Critical(damage) => {
+                    assert_eq!(damage, 18);
+                    crits += 1;
+                }
+            }
+        }
+        assert!(misses > 0 && crits > 0);
+        // The same seed always fights the same way
+        let replay = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..20)
+                .map(|_| roll_hit(attack, 4, &mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(replay(3), replay(3));
+    }
+
+    #[test]
+    fn training_and_equipment_raise_damage() {
+        let mut player = Player::new();
+        let untrained = player_attack(&player, AttackType::Main);
+
+        player.skills.get_mut("Strength").unwrap().level = 40;
+        player.skills.get_mut("Attack").unwrap().level = 40;
+        let trained = player_attack(&player, AttackType::Main);
+        assert!(trained.max_hit > untrained.max_hit);
+        assert!(trained.accuracy > untrained.accuracy);
+
+        player.equipped_weapon = create_items().get(&100004).cloned();
+        let armed = player_attack(&player, AttackType::Main);
+        assert!(armed.max_hit > trained.max_hit);
+        assert!(average_damage(armed, 5) > average_damage(untrained, 5));
+
+        // Feeling mighty hits harder still
+        player
+            .status_effects
+            .apply(StatusEffect::new(StatusKind::Might, 3, 5));
+        assert_eq!(
+            player_attack(&player, AttackType::Main).max_hit,
+            armed.max_hit + 3
+        );
+
+        // Spells only care about Magic
+        let spell = player_attack(&player, AttackType::Magic);
+        player.skills.get_mut("Magic").unwrap().level = 40;
+        assert!(player_attack(&player, AttackType::Magic).max_hit > spell.max_hit);
+    }
+
+    #[test]
+    fn armor_softens_enemy_blows() {
+        let mut player = Player::new();
+        let troll = Enemy::new("Troll", 80, 15, 6, "rare");
+        let bare = average_damage(enemy_attack(&troll), player_defense(&player));
+
+        player.equipped_armor = create_items().get(&100009).cloned();
+        assert!(player_defense(&player) > 0);
+        let armored = average_damage(enemy_attack(&troll), player_defense(&player));
+        assert!(armored < bare);
+
+        let armor = player_defense(&player);
+        player
+            .status_effects
+            .apply(StatusEffect::new(StatusKind::Guard, 3, 5));
+        assert_eq!(player_defense(&player), armor + 3);
+    }
+}
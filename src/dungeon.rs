@@ -0,0 +1,382 @@
+use crate::enemy::{enemy_group, EncounterTable};
+use crate::items::{calculate_loot, create_items, create_loot_tables, CHEST_LOOT_TABLE};
+use crate::map::{Entity, Map, Portal, Tile, OVERWORLD};
+use crate::player::Player;
+use crate::terrain::position_hash;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Floors in the dungeon under the starting campfire. The chest with the
+/// best loot waits on the last one.
+pub const DUNGEON_FLOORS: usize = 3;
+
+const DUNGEON_WIDTH: usize = 48;
+const DUNGEON_HEIGHT: usize = 24;
+const MAX_ROOMS: usize = 9;
+const ROOM_ATTEMPTS: usize = 200;
+
+const DUNGEON_SALT: u64 = 0x4455_4E47;
+
+/// Prefix of the map name of every dungeon floor.
+const FLOOR_PREFIX: &str = "dungeon-";
+
+/// Map name of dungeon floor `floor`, counting from 1.
+pub fn floor_name(floor: usize) -> String {
+    format!("{}{}", FLOOR_PREFIX, floor)
+}
+
+/// Which dungeon floor the map named `name` is, if it is one.
+pub fn floor_of(name: &str) -> Option<usize> {
+    name.strip_prefix(FLOOR_PREFIX)?.parse().ok()
+}
+
+/// A rectangle of floor, not counting the walls around it.
+#[derive(Debug, Clone, Copy)]
+struct Room {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Room {
+    fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// Whether the rooms overlap or would share a wall.
+    fn touches(&self, other: &Room) -> bool {
+        self.x <= other.x + other.width + 1
+            && other.x <= self.x + self.width + 1
+            && self.y <= other.y + other.height + 1
+            && other.y <= self.y + self.height + 1
+    }
+
+    /// A tile away from the walls, so whatever is put there never blocks a
+    /// way into the room.
+    fn random_tile(&self, rng: &mut StdRng) -> (usize, usize) {
+        (
+            rng.gen_range(self.x + 1..self.x + self.width - 1),
+            rng.gen_range(self.y + 1..self.y + self.height - 1),
+        )
+    }
+}
+
+/// Generates floor `floor` of the dungeon of the world with `seed`: rooms
+/// joined by corridors, with stairs up in the first room and stairs down
+/// (or, on the last floor, a chest) in the last. The same seed and floor
+/// always give the same floor.
+pub fn generate_floor(seed: u64, floor: usize) -> Map {
+    let mut rng = StdRng::seed_from_u64(position_hash(seed ^ DUNGEON_SALT, floor, 0));
+    let mut tiles = vec![vec![Tile::Wall; DUNGEON_WIDTH]; DUNGEON_HEIGHT];
+
+    // The stairs up and the way on each need a room of their own
+    let rooms = loop {
+        let rooms = place_rooms(&mut rng);
+        if rooms.len() >= 2 {
+            break rooms;
+        }
+    };
+    for room in &rooms {
+        for row in tiles.iter_mut().skip(room.y).take(room.height) {
+            for tile in row.iter_mut().skip(room.x).take(room.width) {
+                *tile = Tile::Empty;
+            }
+        }
+    }
+    for pair in rooms.windows(2) {
+        let horizontal_first = rng.gen_bool(0.5);
+        carve_corridor(
+            &mut tiles,
+            pair[0].center(),
+            pair[1].center(),
+            horizontal_first,
+        );
+    }
+    add_doors(&mut tiles, &rooms, &mut rng);
+
+    // Stairs up in the first room, with the player arriving next to them
+    let (up_x, up_y) = rooms[0].center();
+    tiles[up_y][up_x] = Tile::StairsUp;
+    let mut entities = Vec::new();
+    let above = if floor <= 1 {
+        OVERWORLD.to_string()
+    } else {
+        floor_name(floor - 1)
+    };
+    let mut portals = vec![portal(up_x, up_y, above)];
+
+    let (end_x, end_y) = rooms[rooms.len() - 1].center();
+    if floor < DUNGEON_FLOORS {
+        tiles[end_y][end_x] = Tile::StairsDown;
+        portals.push(portal(end_x, end_y, floor_name(floor + 1)));
+    } else {
+        entities.push((end_x, end_y, Entity::Chest));
+    }
+
+    // Every other room has a guard or two, and one of them a chest
+    let chest_room = rng.gen_range(1..rooms.len());
+    for (index, room) in rooms.iter().enumerate().skip(1) {
+        let mut contents = vec![Entity::Enemy; rng.gen_range(1..=2)];
+        if index == chest_room {
+            contents.push(Entity::Chest);
+        }
+        for entity in contents {
+            let (x, y) = room.random_tile(&mut rng);
+            let taken = entities
+                .iter()
+                .any(|&(taken_x, taken_y, _)| (taken_x, taken_y) == (x, y));
+            if tiles[y][x] == Tile::Empty && !taken {
+                entities.push((x, y, entity));
+            }
+        }
+    }
+
+    let rows: Vec<String> = tiles
+        .iter()
+        .map(|row| row.iter().map(|tile| tile.to_char()).collect())
+        .collect();
+    let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+    let mut map = Map::from_layout(&floor_name(floor), &rows, portals);
+    map.seed = seed;
+    for (x, y, entity) in entities {
+        map.place_entity(x, y, entity);
+    }
+    // Respawning on this floor puts the player back by the stairs
+    map.campfire_x = up_x + 1;
+    map.campfire_y = up_y + 1;
+    map.place_player(up_x + 1, up_y);
+    map
+}
+
+fn portal(x: usize, y: usize, target: String) -> Portal {
+    Portal {
+        x,
+        y,
+        target,
+        arrival: None,
+    }
+}
+
+/// Rooms that don't touch each other, ordered from west to east so the
+/// corridors between neighbours rarely cross.
+fn place_rooms(rng: &mut StdRng) -> Vec<Room> {
+    let mut rooms: Vec<Room> = Vec::new();
+    for _ in 0..ROOM_ATTEMPTS {
+        if rooms.len() == MAX_ROOMS {
+            break;
+        }
+        let (width, height) = (rng.gen_range(4..=10), rng.gen_range(3..=6));
+        let room = Room {
+            x: rng.gen_range(1..DUNGEON_WIDTH - width - 1),
+            y: rng.gen_range(1..DUNGEON_HEIGHT - height - 1),
+            width,
+            height,
+        };
+        if !rooms.iter().any(|other| other.touches(&room)) {
+            rooms.push(room);
+        }
+    }
+    rooms.sort_by_key(|room| room.x);
+    rooms
+}
+
+/// Digs an L shaped corridor between two points.
+fn carve_corridor(
+    tiles: &mut [Vec<Tile>],
+    (from_x, from_y): (usize, usize),
+    (to_x, to_y): (usize, usize),
+    horizontal_first: bool,
+) {
+    let corner = if horizontal_first {
+        (to_x, from_y)
+    } else {
+        (from_x, to_y)
+    };
+    for (start, end) in [((from_x, from_y), corner), (corner, (to_x, to_y))] {
+        let (left, right) = (start.0.min(end.0), start.0.max(end.0));
+        let (top, bottom) = (start.1.min(end.1), start.1.max(end.1));
+        for row in &mut tiles[top..=bottom] {
+            for tile in &mut row[left..=right] {
+                *tile = Tile::Empty;
+            }
+        }
+    }
+}
+
+/// Puts doors in most of the one tile wide gaps where a corridor enters a
+/// room.
+fn add_doors(tiles: &mut [Vec<Tile>], rooms: &[Room], rng: &mut StdRng) {
+    for room in rooms {
+        let (left, right) = (room.x - 1, room.x + room.width);
+        let (top, bottom) = (room.y - 1, room.y + room.height);
+        let mut gaps = Vec::new();
+        for x in room.x..room.x + room.width {
+            for y in [top, bottom] {
+                if tiles[y][x] == Tile::Empty
+                    && tiles[y][x - 1] == Tile::Wall
+                    && tiles[y][x + 1] == Tile::Wall
+                {
+                    gaps.push((x, y));
+                }
+            }
+        }
+        for y in room.y..room.y + room.height {
+            for x in [left, right] {
+                if tiles[y][x] == Tile::Empty
+                    && tiles[y - 1][x] == Tile::Wall
+                    && tiles[y + 1][x] == Tile::Wall
+                {
+                    gaps.push((x, y));
+                }
+            }
+        }
+        for (x, y) in gaps {
+            if rng.gen_bool(0.7) {
+                tiles[y][x] = Tile::Door;
+            }
+        }
+    }
+}
+
+/// Enemies guarding dungeon floor `floor`. Deeper floors hold tougher
+/// enemies.
+pub fn floor_enemies(floor: usize) -> EncounterTable {
+    let groups = match floor {
+        0 | 1 => vec![
+            (enemy_group(&["Goblin"]), 4),
+            (enemy_group(&["Goblin", "Goblin"]), 1),
+            (enemy_group(&["Skeleton"]), 2),
+        ],
+        2 => vec![
+            (enemy_group(&["Goblin", "Goblin"]), 2),
+            (enemy_group(&["Skeleton"]), 4),
+            (enemy_group(&["Orc"]), 3),
+        ],
+        _ => vec![
+            (enemy_group(&["Orc", "Skeleton"]), 3),
+            (enemy_group(&["Skeleton", "Skeleton"]), 2),
+            (enemy_group(&["Troll"]), 2),
+        ],
+    };
+    // Guards stand on their own tiles, nothing wanders into the player
+    EncounterTable { chance: 0, groups }
+}
+
+/// Opens the chest at `(x, y)`, moving its loot into the inventory. The
+/// chest is gone afterwards.
+pub fn open_chest(player: &mut Player, map: &mut Map, x: usize, y: usize) -> String {
+    let mut loot = create_loot_tables()
+        .get(CHEST_LOOT_TABLE)
+        .map(calculate_loot)
+        .unwrap_or_default();
+    // A chest is never empty, at worst it holds a single coin
+    if loot.is_empty() {
+        loot.insert(100001, 1);
+    }
+    player.add_loot(&loot);
+    map.remove_entity(x, y);
+
+    let items = create_items();
+    let mut found: Vec<String> = loot
+        .iter()
+        .filter_map(|(id, quantity)| {
+            items
+                .get(id)
+                .map(|item| format!("({}) {}", quantity, item.name))
+        })
+        .collect();
+    found.sort();
+    format!("You open the chest and find {}.", found.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Every tile the player can reach from where they arrive, walking
+    /// through doors and defeated guards.
+    fn reachable(map: &Map) -> Vec<Vec<bool>> {
+        let mut seen = vec![vec![false; map.width]; map.height];
+        let mut queue = VecDeque::from([(map.player_x, map.player_y)]);
+        seen[map.player_y][map.player_x] = true;
+        while let Some((x, y)) = queue.pop_front() {
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                let passable = (map.tile(nx, ny).is_walkable() || map.tile(nx, ny) == Tile::Door)
+                    && map.entity_at(nx, ny) != Some(Entity::Chest);
+                if passable && !seen[ny][nx] {
+                    seen[ny][nx] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn floors_are_the_same_for_the_same_seed() {
+        assert_eq!(
+            generate_floor(42, 1).serialize_map(),
+            generate_floor(42, 1).serialize_map()
+        );
+        assert_ne!(
+            generate_floor(42, 1).serialize_map(),
+            generate_floor(43, 1).serialize_map()
+        );
+    }
+
+    #[test]
+    fn stairs_and_chests_can_be_reached() {
+        for seed in 0..40 {
+            for floor in 1..=DUNGEON_FLOORS {
+                let map = generate_floor(seed, floor);
+                assert_eq!(map.tile(map.player_x - 1, map.player_y), Tile::StairsUp);
+                let seen = reachable(&map);
+                let mut targets = 0;
+                for y in 1..map.height - 1 {
+                    for x in 1..map.width - 1 {
+                        let chest = map.entity_at(x, y) == Some(Entity::Chest);
+                        if map.tile(x, y) != Tile::StairsDown && !chest {
+                            continue;
+                        }
+                        targets += 1;
+                        let next_to_player = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                            .iter()
+                            .any(|&(nx, ny)| seen[ny][nx]);
+                        assert!(
+                            next_to_player,
+                            "seed {} floor {}: ({}, {})",
+                            seed, floor, x, y
+                        );
+                    }
+                }
+                assert!(targets >= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn stairs_link_the_floors() {
+        let first = generate_floor(7, 1);
+        assert_eq!(first.portals[0].target, OVERWORLD);
+        assert_eq!(first.portals[1].target, floor_name(2));
+        let last = generate_floor(7, DUNGEON_FLOORS);
+        assert_eq!(last.portals.len(), 1);
+        assert_eq!(floor_of(&last.name), Some(DUNGEON_FLOORS));
+        assert_eq!(floor_of("town"), None);
+    }
+
+    #[test]
+    fn opened_chests_are_emptied_into_the_inventory() {
+        let mut player = Player::new();
+        let mut map = Map::from_layout(&floor_name(1), &["====", "=P$=", "===="], Vec::new());
+        assert_eq!(map.entity_at(2, 1), Some(Entity::Chest));
+        let before: u32 = player.inventory.values().sum();
+
+        let message = open_chest(&mut player, &mut map, 2, 1);
+        assert!(message.starts_with("You open the chest and find"));
+        assert!(player.inventory.values().sum::<u32>() > before);
+        assert_eq!(map.entity_at(2, 1), None);
+    }
+}
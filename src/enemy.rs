@@ -1,20 +1,108 @@
+use rand::Rng;
 use serde::{Serialize, Deserialize};
+use crate::damage::{enemy_attack, player_defense, roll_hit, Attack, Hit};
+use crate::player::Player;
+use crate::status::{StatusEffects, StatusKind};
+use crate::terrain::Biome;
+
+/// Something an enemy can do instead of a plain attack.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Ability {
+    HeavyHit,    // A clumsy blow that hits twice as hard
+    Block,       // Guards until its next turn
+    Heal,        // Recovers a quarter of its health
+    Flee,        // Runs from the fight
+    CallForHelp, // Another of its kind joins the fight
+    Poison,      // A blow that poisons the player
+    Rend,        // A bite that leaves the player bleeding
+}
+
+/// How an enemy picks among its abilities each turn.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Behavior {
+    /// Uses any of its abilities now and then.
+    Balanced,
+    /// Swings heavy blows and patches itself up when hurt.
+    Brute,
+    /// Tears at its prey, howls for the pack when hurt, and runs when close
+    /// to death.
+    Pack,
+    /// Raises its guard often and never gives ground.
+    Guardian,
+    /// Poisons its prey and slips away when the fight turns.
+    Skirmisher,
+}
+
+impl Behavior {
+    /// The ability `enemy` uses this turn, or `None` for a plain attack.
+    pub fn choose(self, enemy: &Enemy, rng: &mut impl Rng) -> Option<Ability> {
+        let has = |ability| enemy.abilities.contains(&ability);
+        let hurt = enemy.health * 2 <= enemy.max_health;
+        let near_death = enemy.health * 4 <= enemy.max_health;
+        let ability = match self {
+            Behavior::Balanced => {
+                let usable: Vec<Ability> = enemy
+                    .abilities
+                    .iter()
+                    .copied()
+                    .filter(|&ability| match ability {
+                        Ability::Flee => near_death,
+                        Ability::Heal => hurt,
+                        _ => true,
+                    })
+                    .collect();
+                if usable.is_empty() || !rng.gen_bool(0.25) {
+                    return None;
+                }
+                usable[rng.gen_range(0..usable.len())]
+            }
+            Behavior::Brute if hurt && has(Ability::Heal) && rng.gen_bool(0.4) => Ability::Heal,
+            Behavior::Brute if rng.gen_bool(0.3) => Ability::HeavyHit,
+            Behavior::Pack if near_death => Ability::Flee,
+            Behavior::Pack if hurt && rng.gen_bool(0.3) => Ability::CallForHelp,
+            Behavior::Pack if rng.gen_bool(0.25) => Ability::Rend,
+            Behavior::Guardian if rng.gen_bool(0.3) => Ability::Block,
+            Behavior::Guardian if rng.gen_bool(0.2) => Ability::HeavyHit,
+            Behavior::Skirmisher if near_death && rng.gen_bool(0.5) => Ability::Flee,
+            Behavior::Skirmisher if rng.gen_bool(0.3) => Ability::Poison,
+            _ => return None,
+        };
+        has(ability).then_some(ability)
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Enemy {
     pub name: String,
     pub health: i32,
+    pub max_health: i32,
     pub attack: i32,
+    pub defense: i32,
+    pub accuracy: i32,
+    pub speed: i32, // Turns come as often as speed allows; the player has a speed of 10
+    pub abilities: Vec<Ability>,
+    pub behavior: Behavior,
     pub loot_table: String, // Added loot_table
+    #[serde(default)]
+    pub status_effects: StatusEffects,
 }
 
 impl Enemy {
-    pub fn new(name: &str, health: i32, attack: i32, loot_table: &str) -> Enemy {
+    /// A plain enemy as quick and as accurate as its attack, with no
+    /// abilities.
+    pub fn new(name: &str, health: i32, attack: i32, defense: i32, loot_table: &str) -> Enemy {
         Enemy {
             name: name.to_string(),
             health,
+            max_health: health,
             attack,
+            defense,
+            accuracy: attack,
+            speed: 10,
+            abilities: Vec::new(),
+            behavior: Behavior::Balanced,
             loot_table: loot_table.to_string(), // Initialize loot_table here
+            status_effects: StatusEffects::default(),
         }
     }
 
@@ -25,26 +113,234 @@ impl Enemy {
         }
     }
 
+    /// Heals by `amount`, up to full health, returning how much was healed.
+    pub fn heal(&mut self, amount: i32) -> i32 {
+        let healed = amount.min(self.max_health - self.health).max(0);
+        self.health += healed;
+        healed
+    }
+
+    /// Passes a turn for the enemy's status effects, like
+    /// `Player::tick_status_effects`.
+    pub fn tick_status_effects(&mut self) -> Vec<(StatusKind, i32)> {
+        let changes = self.status_effects.tick();
+        for (_, change) in &changes {
+            self.health = (self.health + change).clamp(0, self.max_health);
+        }
+        changes
+    }
+
     pub fn is_defeated(&self) -> bool {
         self.health <= 0
     }
 
-    pub fn attack_player(&self, player_health: &mut i32) {
-        *player_health -= self.attack;
-        if *player_health < 0 {
-            *player_health = 0;
-        }
+    /// Attacks the player, whose armor makes the blow less likely to land
+    /// and softer when it does.
+    pub fn attack_player(&self, player: &mut Player, rng: &mut impl Rng) -> Hit {
+        self.strike(enemy_attack(self), player, rng)
+    }
+
+    /// Like `attack_player`, but with a heavy blow.
+    pub fn heavy_hit(&self, player: &mut Player, rng: &mut impl Rng) -> Hit {
+        self.strike(enemy_attack(self).heavy(), player, rng)
+    }
+
+    fn strike(&self, attack: Attack, player: &mut Player, rng: &mut impl Rng) -> Hit {
+        let hit = roll_hit(attack, player_defense(player), rng);
+        player.health = (player.health - hit.damage()).max(0);
+        hit
     }
 }
 
 // Function to create some basic enemies
 pub fn basic_enemies() -> Vec<Enemy> {
     vec![
-        Enemy::new("Goblin", 30, 5, "common"),       // Goblin drops from common loot table
-        Enemy::new("Orc", 50, 10, "uncommon"),       // Orc drops from uncommon loot table
-        Enemy::new("Bandit", 40, 8, "common_food"),       // Bandit drops from common loot table
-        Enemy::new("Wolf", 35, 7, "uncommon"),    // Wolf drops from common_food loot table
-        Enemy::new("Skeleton", 45, 9, "uncommon"),   // Skeleton drops from uncommon loot table
-        Enemy::new("Troll", 80, 15, "rare"),         // Troll drops from rare loot table
+        // Goblin drops from common loot table
+        Enemy {
+            accuracy: 6,
+            speed: 12,
+            abilities: vec![Ability::CallForHelp, Ability::Flee],
+            behavior: Behavior::Pack,
+            ..Enemy::new("Goblin", 30, 5, 1, "common")
+        },
+        // Orc drops from uncommon loot table
+        Enemy {
+            accuracy: 8,
+            speed: 9,
+            abilities: vec![Ability::HeavyHit, Ability::Block],
+            behavior: Behavior::Brute,
+            ..Enemy::new("Orc", 50, 10, 4, "uncommon")
+        },
+        // Bandit drops from common_food loot table
+        Enemy {
+            accuracy: 10,
+            speed: 11,
+            abilities: vec![Ability::Poison, Ability::Flee],
+            behavior: Behavior::Skirmisher,
+            ..Enemy::new("Bandit", 40, 8, 2, "common_food")
+        },
+        // Wolf drops from uncommon loot table
+        Enemy {
+            accuracy: 9,
+            speed: 16,
+            abilities: vec![Ability::Rend, Ability::CallForHelp, Ability::Flee],
+            behavior: Behavior::Pack,
+            ..Enemy::new("Wolf", 35, 7, 2, "uncommon")
+        },
+        // Skeleton drops from uncommon loot table
+        Enemy {
+            accuracy: 9,
+            speed: 8,
+            abilities: vec![Ability::Block, Ability::HeavyHit],
+            behavior: Behavior::Guardian,
+            ..Enemy::new("Skeleton", 45, 9, 5, "uncommon")
+        },
+        // Troll drops from rare loot table
+        Enemy {
+            accuracy: 12,
+            speed: 6,
+            abilities: vec![Ability::HeavyHit, Ability::Heal],
+            behavior: Behavior::Brute,
+            ..Enemy::new("Troll", 80, 15, 6, "rare")
+        },
     ]
 }
+
+/// Who can be met in a biome, and how often.
+pub struct EncounterTable {
+    pub chance: u8,                     // Percent chance of an encounter per move
+    pub groups: Vec<(Vec<Enemy>, u32)>, // Enemies met together and their weight
+}
+
+impl EncounterTable {
+    /// Picks a group with probability proportional to its weight.
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<Vec<Enemy>> {
+        let total: u32 = self.groups.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut choice = rng.gen_range(0..total);
+        for (group, weight) in &self.groups {
+            if choice < *weight {
+                return Some(group.clone());
+            }
+            choice -= weight;
+        }
+        None
+    }
+}
+
+/// The enemies called `names`, in that order.
+pub fn enemy_group(names: &[&str]) -> Vec<Enemy> {
+    names
+        .iter()
+        .map(|&name| {
+            basic_enemies()
+                .into_iter()
+                .find(|enemy| enemy.name == name)
+                .expect("unknown enemy in encounter table")
+        })
+        .collect()
+}
+
+// Function to look up the encounter table for a biome
+pub fn encounter_table(biome: Biome) -> EncounterTable {
+    let group = enemy_group;
+    let (chance, groups) = match biome {
+        Biome::Grassland => (
+            1,
+            vec![
+                (group(&["Bandit"]), 3),
+                (group(&["Goblin"]), 3),
+                (group(&["Goblin", "Goblin", "Goblin"]), 1),
+                (group(&["Bandit", "Wolf"]), 1),
+                (group(&["Wolf"]), 2),
+            ],
+        ),
+        Biome::Forest => (
+            2,
+            vec![
+                (group(&["Wolf"]), 4),
+                (group(&["Wolf", "Wolf"]), 1),
+                (group(&["Goblin"]), 3),
+                (group(&["Bandit", "Wolf"]), 2),
+            ],
+        ),
+        Biome::Hills => (
+            2,
+            vec![
+                (group(&["Orc"]), 4),
+                (group(&["Goblin", "Goblin"]), 3),
+                (group(&["Troll"]), 1),
+            ],
+        ),
+        Biome::Beach => (1, vec![(group(&["Skeleton"]), 3), (group(&["Bandit"]), 2)]),
+        Biome::Mountains => (
+            3,
+            vec![
+                (group(&["Troll"]), 3),
+                (group(&["Orc"]), 2),
+                (group(&["Orc", "Orc"]), 1),
+            ],
+        ),
+        // Nobody walks on water
+        Biome::Lake => (0, Vec::new()),
+    };
+    EncounterTable { chance, groups }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encounters_follow_the_biome() {
+        let mut rng = rand::thread_rng();
+        let forest = encounter_table(Biome::Forest);
+        for _ in 0..100 {
+            let group = forest.pick(&mut rng).unwrap();
+            assert!(!group.is_empty());
+            for enemy in group {
+                assert!(["Wolf", "Goblin", "Bandit"].contains(&enemy.name.as_str()));
+            }
+        }
+        assert!(encounter_table(Biome::Lake).pick(&mut rng).is_none());
+        assert!(encounter_table(Biome::Hills)
+            .groups
+            .iter()
+            .any(|(group, _)| group.iter().any(|enemy| enemy.name == "Troll")));
+        // Some enemies come in packs
+        assert!(encounter_table(Biome::Grassland)
+            .groups
+            .iter()
+            .any(|(group, _)| group.len() > 1));
+    }
+
+    #[test]
+    fn behaviors_only_pick_abilities_the_enemy_has() {
+        let mut rng = rand::thread_rng();
+        let plain = Enemy::new("Goblin", 30, 5, 1, "common");
+        for behavior in [Behavior::Balanced, Behavior::Brute, Behavior::Pack] {
+            for _ in 0..100 {
+                assert_eq!(behavior.choose(&plain, &mut rng), None);
+            }
+        }
+
+        let troll = basic_enemies().into_iter().find(|enemy| enemy.name == "Troll").unwrap();
+        let picks: Vec<Option<Ability>> = (0..200)
+            .map(|_| troll.behavior.choose(&troll, &mut rng))
+            .collect();
+        assert!(picks.contains(&Some(Ability::HeavyHit)));
+        assert!(picks.contains(&None));
+        // A troll at full health has nothing to heal
+        assert!(!picks.contains(&Some(Ability::Heal)));
+    }
+
+    #[test]
+    fn pack_animals_run_when_close_to_death() {
+        let mut rng = rand::thread_rng();
+        let mut wolf = basic_enemies().into_iter().find(|enemy| enemy.name == "Wolf").unwrap();
+        wolf.take_damage(wolf.max_health - 1);
+        assert_eq!(wolf.behavior.choose(&wolf, &mut rng), Some(Ability::Flee));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::items::create_items;
+use crate::map::{Map, Tile};
+use crate::player::Player;
+use rand::Rng;
+
+pub const FISHING_ROD: u32 = 100013;
+pub const FISHING_BAIT: u32 = 100021;
+pub const BRONZE_HATCHET: u32 = 100011;
+pub const BRONZE_PICKAXE: u32 = 100010;
+
+/// World ticks before a felled tree or a mined rock grows back.
+pub const TREE_REGROW_TICKS: u64 = 300;
+pub const ROCK_REGROW_TICKS: u64 = 500;
+
+/// Something a gathering skill can produce.
+struct Yield {
+    item_id: u32,
+    level: i32,
+    experience: f64,
+    // Relative chance among the yields the player has the level for
+    weight: u32,
+}
+
+const FISH: [Yield; 3] = [
+    Yield {
+        item_id: 100015, // Raw Shrimp
+        level: 1,
+        experience: 10.0,
+        weight: 6,
+    },
+    Yield {
+        item_id: 100023, // Raw Trout
+        level: 15,
+        experience: 50.0,
+        weight: 3,
+    },
+    Yield {
+        item_id: 100025, // Raw Salmon
+        level: 30,
+        experience: 70.0,
+        weight: 2,
+    },
+];
+
+const LOGS: [Yield; 1] = [Yield {
+    item_id: 100022, // Log
+    level: 1,
+    experience: 25.0,
+    weight: 1,
+}];
+
+const ORES: [Yield; 3] = [
+    Yield {
+        item_id: 100027, // Copper Ore
+        level: 1,
+        experience: 17.5,
+        weight: 4,
+    },
+    Yield {
+        item_id: 100028, // Tin Ore
+        level: 1,
+        experience: 17.5,
+        weight: 4,
+    },
+    Yield {
+        item_id: 100029, // Iron Ore
+        level: 15,
+        experience: 35.0,
+        weight: 3,
+    },
+];
+
+/// Uses the faced tile at `(x, y)` if a gathering skill works on it.
+/// Returns `None` for tiles that have nothing to gather.
+pub fn gather<R: Rng>(
+    player: &mut Player,
+    map: &mut Map,
+    (x, y, tile): (usize, usize, Tile),
+    rng: &mut R,
+) -> Option<String> {
+    let message = match tile {
+        Tile::FishingSpot => fish(player, rng),
+        Tile::Tree => chop_tree(player, map, x, y, rng),
+        Tile::Rock => mine_rock(player, map, x, y, rng),
+        Tile::Stump => "Only a stump is left. A new tree will grow here in time.".to_string(),
+        Tile::DepletedRock => "This rock is mined out. It will fill with ore again.".to_string(),
+        _ => return None,
+    };
+    Some(message)
+}
+
+/// Chance that a cast catches anything, growing with Fishing level.
+fn catch_chance(level: i32) -> f64 {
+    (0.5 + level as f64 * 0.005).min(0.95)
+}
+
+/// World ticks a chop or a swing of the pickaxe takes at `level`.
+pub fn gathering_ticks(level: i32) -> u64 {
+    (10 - level / 10).max(2) as u64
+}
+
+/// Casts once at a fishing spot. Every cast uses one bait; a catch adds the
+/// fish to the inventory and trains Fishing.
+pub fn fish<R: Rng>(player: &mut Player, rng: &mut R) -> String {
+    if !player.inventory.contains_key(&FISHING_ROD) {
+        return "You need a Fishing Rod to fish here.".to_string();
+    }
+    if !player.remove_item(FISHING_BAIT, 1) {
+        return "You have no Fishing Bait left.".to_string();
+    }
+
+    let level = skill_level(player, "Fishing");
+    if !rng.gen_bool(catch_chance(level)) {
+        return "You cast your line but nothing bites.".to_string();
+    }
+    let (name, experience) = collect(player, "Fishing", &FISH, rng);
+    format!("You catch a {}. (+{} Fishing XP)", name, experience)
+}
+
+/// Fells the tree at `(x, y)`, leaving a stump that grows back later.
+pub fn chop_tree<R: Rng>(
+    player: &mut Player,
+    map: &mut Map,
+    x: usize,
+    y: usize,
+    rng: &mut R,
+) -> String {
+    if !player.inventory.contains_key(&BRONZE_HATCHET) {
+        return "You need a Bronze Hatchet to chop trees.".to_string();
+    }
+    let ticks = gathering_ticks(skill_level(player, "Woodcutting"));
+    map.advance_time(ticks);
+    map.deplete_tile(x, y, Tile::Stump, TREE_REGROW_TICKS);
+    let (name, experience) = collect(player, "Woodcutting", &LOGS, rng);
+    format!(
+        "After {} ticks of chopping you get a {}. (+{} Woodcutting XP)",
+        ticks, name, experience
+    )
+}
+
+/// Mines the rock at `(x, y)` for ore, leaving it depleted for a while.
+pub fn mine_rock<R: Rng>(
+    player: &mut Player,
+    map: &mut Map,
+    x: usize,
+    y: usize,
+    rng: &mut R,
+) -> String {
+    if !player.inventory.contains_key(&BRONZE_PICKAXE) {
+        return "You need a Bronze Pickaxe to mine rocks.".to_string();
+    }
+    let ticks = gathering_ticks(skill_level(player, "Mining"));
+    map.advance_time(ticks);
+    map.deplete_tile(x, y, Tile::DepletedRock, ROCK_REGROW_TICKS);
+    let (name, experience) = collect(player, "Mining", &ORES, rng);
+    format!(
+        "After {} ticks of mining you get some {}. (+{} Mining XP)",
+        ticks, name, experience
+    )
+}
+
+fn skill_level(player: &Player, skill: &str) -> i32 {
+    player.skills.get(skill).map_or(1, |skill| skill.level)
+}
+
+/// Rolls one of the yields the player has the level for, adds it to the
+/// inventory and trains `skill`. Returns the item name and the XP gained.
+fn collect<R: Rng>(
+    player: &mut Player,
+    skill: &str,
+    table: &[Yield],
+    rng: &mut R,
+) -> (String, f64) {
+    let level = skill_level(player, skill);
+    let available: Vec<&Yield> = table.iter().filter(|entry| entry.level <= level).collect();
+    let total: u32 = available.iter().map(|entry| entry.weight).sum();
+    let mut roll = rng.gen_range(0..total);
+    let picked = available
+        .iter()
+        .find(|entry| {
+            if roll < entry.weight {
+                return true;
+            }
+            roll -= entry.weight;
+            false
+        })
+        .expect("roll is below the total weight");
+
+    player.add_item_to_inventory(picked.item_id, 1);
+    if let Some(skill) = player.skills.get_mut(skill) {
+        skill.add_experience(picked.experience);
+    }
+    let name = create_items()
+        .get(&picked.item_id)
+        .map_or_else(|| "something".to_string(), |item| item.name.clone());
+    (name, picked.experience)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Direction;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn fishing_uses_bait_and_trains_fishing() {
+        let mut player = Player::new();
+        player.inventory.insert(FISHING_BAIT, 20);
+        player.inventory.remove(&100015);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..20 {
+            fish(&mut player, &mut rng);
+        }
+        assert!(!player.inventory.contains_key(&FISHING_BAIT));
+        assert!(player.inventory.get(&100015).copied().unwrap_or(0) > 0);
+        assert!(player.skills["Fishing"].experience > 0.0);
+        // Trout needs level 15, which twenty casts can't reach
+        assert!(!player.inventory.contains_key(&100023));
+        assert_eq!(
+            fish(&mut player, &mut rng),
+            "You have no Fishing Bait left."
+        );
+    }
+
+    #[test]
+    fn higher_levels_catch_better_fish() {
+        let mut player = Player::new();
+        player.inventory.insert(FISHING_BAIT, 200);
+        player.skills.get_mut("Fishing").unwrap().level = 40;
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for _ in 0..200 {
+            fish(&mut player, &mut rng);
+        }
+        assert!(player.inventory.contains_key(&100023));
+        assert!(player.inventory.contains_key(&100025));
+    }
+
+    #[test]
+    fn fishing_needs_a_rod() {
+        let mut player = Player::new();
+        player.inventory.remove(&FISHING_ROD);
+        player.inventory.insert(FISHING_BAIT, 5);
+        assert_eq!(
+            fish(&mut player, &mut StdRng::seed_from_u64(3)),
+            "You need a Fishing Rod to fish here."
+        );
+        assert_eq!(player.inventory[&FISHING_BAIT], 5);
+    }
+
+    #[test]
+    fn felled_trees_leave_a_stump_that_regrows() {
+        let mut player = Player::new();
+        let mut map = Map::deserialize_map("RPGMAP 1 2 1 0 0\n.t\n", 0, 0).unwrap();
+        let mut rng = StdRng::seed_from_u64(4);
+        let logs = player.inventory[&100022];
+
+        let faced = map.faced_tile(Direction::Right).unwrap();
+        assert!(gather(&mut player, &mut map, faced, &mut rng).is_some());
+        assert_eq!(player.inventory[&100022], logs + 1);
+        assert_eq!(player.skills["Woodcutting"].experience, 25.0);
+        assert_eq!(map.tile(1, 0), Tile::Stump);
+        assert_eq!(map.world_time, gathering_ticks(1));
+
+        map.advance_time(TREE_REGROW_TICKS - 1);
+        assert_eq!(map.tile(1, 0), Tile::Stump);
+        map.advance_time(1);
+        assert_eq!(map.tile(1, 0), Tile::Tree);
+        assert!(map.regrowing.is_empty());
+    }
+
+    #[test]
+    fn mining_needs_a_pickaxe_and_yields_ore() {
+        let mut player = Player::new();
+        let mut map = Map::deserialize_map("RPGMAP 1 2 1 0 0\n.r\n", 0, 0).unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+
+        player.inventory.remove(&BRONZE_PICKAXE);
+        assert_eq!(
+            mine_rock(&mut player, &mut map, 1, 0, &mut rng),
+            "You need a Bronze Pickaxe to mine rocks."
+        );
+        assert_eq!(map.tile(1, 0), Tile::Rock);
+
+        player.add_item_to_inventory(BRONZE_PICKAXE, 1);
+        mine_rock(&mut player, &mut map, 1, 0, &mut rng);
+        let ores = [100027, 100028]
+            .iter()
+            .filter_map(|id| player.inventory.get(id))
+            .sum::<u32>();
+        assert_eq!(ores, 1);
+        assert_eq!(map.tile(1, 0), Tile::DepletedRock);
+        assert!(player.skills["Mining"].experience > 0.0);
+    }
+
+    #[test]
+    fn higher_levels_gather_faster() {
+        assert_eq!(gathering_ticks(1), 10);
+        assert_eq!(gathering_ticks(50), 5);
+        assert_eq!(gathering_ticks(99), 2);
+    }
+}
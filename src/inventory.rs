@@ -1,4 +1,4 @@
-use crate::items::{create_items, Item, ItemType};
+use crate::items::{cooked_version, create_items, Effect, Item, ItemType};
 use crate::player::Player;
 use std::io::{self, Write};
 
@@ -17,7 +17,7 @@ pub fn display_inventory(player: &mut Player, filter_type: Option<ItemType>) ->
         for (item_id, quantity) in &player.inventory {
             if let Some(item) = items.get(item_id) {
                 // Borrow `filter_type` using `as_ref()` to prevent moving
-                if filter_type.as_ref().map_or(true, |f| item.item_type == *f) && *quantity > 0 {
+                if filter_type.as_ref().is_none_or(|f| item.item_type == *f) && *quantity > 0 {
                     println!("{:<20} x{:<8}", item.name, quantity);
                     found = true; // Set flag if at least one item is displayed
                 }
@@ -65,10 +65,8 @@ pub fn display_inventory(player: &mut Player, filter_type: Option<ItemType>) ->
                                     continue;
                                 }
                                 _ => {
-                                    // Handle other item types if needed
-                                    println!("\nCan't use that type of item.");
-                                    println!("\nPress Enter to continue...");
-                                    let _ = io::stdin().read_line(&mut String::new());
+                                    // Equip or unequip equipment, describe anything else
+                                    interact_with_item(player, item);
                                     continue;
                                 }
                             }
@@ -204,6 +202,7 @@ pub fn interact_with_consumable(player: &mut Player, item: &Item) {
     }
 }
 
+#[allow(dead_code)]
 pub fn use_item(player: &mut Player, item_name: &str) -> Option<String> {
     let items = create_items();
 
@@ -222,21 +221,7 @@ pub fn use_item(player: &mut Player, item_name: &str) -> Option<String> {
 
                         let mut message = format!("You ate the {}!", item.name);
                         if let Some(effect) = &item.effect {
-                            if effect.health_change != 0 {
-                                player.health = (player.health + effect.health_change)
-                                    .min(player.max_health)
-                                    .max(0);
-                                message.push_str(&format!(
-                                    "\nHealth restored: {}. Current health: {}/{}",
-                                    effect.health_change, player.health, player.max_health
-                                ));
-                            }
-                            if effect.stamina_change != 0 {
-                                message.push_str(&format!(
-                                    "\nStamina change: {}",
-                                    effect.stamina_change
-                                ));
-                            }
+                            message.push_str(&apply_effect(player, effect));
                         }
                         return Some(message);
                     }
@@ -268,18 +253,7 @@ pub fn consume_item(player: &mut Player, item_name: &str) -> Option<String> {
 
                 let mut message = format!("You ate the {}!", item.name);
                 if let Some(effect) = &item.effect {
-                    if effect.health_change != 0 {
-                        player.health = (player.health + effect.health_change)
-                            .min(player.max_health)
-                            .max(0);
-                        message.push_str(&format!(
-                            "\nHealth restored: {}. Current health: {}/{}",
-                            effect.health_change, player.health, player.max_health
-                        ));
-                    }
-                    if effect.stamina_change != 0 {
-                        message.push_str(&format!("\nStamina change: {}", effect.stamina_change));
-                    }
+                    message.push_str(&apply_effect(player, effect));
                 }
                 return Some(message);
             }
@@ -288,6 +262,54 @@ pub fn consume_item(player: &mut Player, item_name: &str) -> Option<String> {
     None
 }
 
+/// Applies what eating something does, returning a line for each change.
+fn apply_effect(player: &mut Player, effect: &Effect) -> String {
+    let mut message = String::new();
+    if effect.health_change != 0 {
+        player.health = (player.health + effect.health_change)
+            .min(player.max_health)
+            .max(0);
+        message.push_str(&format!(
+            "\nHealth restored: {}. Current health: {}/{}",
+            effect.health_change, player.health, player.max_health
+        ));
+    }
+    if effect.stamina_change != 0 {
+        message.push_str(&format!("\nStamina change: {}", effect.stamina_change));
+    }
+    if let Some(status) = effect.status {
+        player.status_effects.apply(status);
+        message.push_str(&format!("\nYou feel {} for {} turns.", status.kind, status.turns));
+    }
+    message
+}
+
+// Cook every raw food item in the inventory, returning a message describing the result
+pub fn cook_raw_food(player: &mut Player) -> String {
+    let items = create_items();
+    let raw_food: Vec<(u32, u32, u32)> = player
+        .inventory
+        .iter()
+        .filter_map(|(&item_id, &quantity)| {
+            cooked_version(item_id).map(|cooked_id| (item_id, cooked_id, quantity))
+        })
+        .collect();
+
+    if raw_food.is_empty() {
+        return "You have nothing to cook.".to_string();
+    }
+
+    let mut cooked = Vec::new();
+    for (raw_id, cooked_id, quantity) in raw_food {
+        player.remove_item(raw_id, quantity);
+        player.add_item_to_inventory(cooked_id, quantity);
+        if let Some(item) = items.get(&cooked_id) {
+            cooked.push(format!("({}) {}", quantity, item.name));
+        }
+    }
+    format!("Cooked {}.", cooked.join(", "))
+}
+
 pub fn handle_eat_command(player: &mut Player, item_name: &str) -> String {
     if let Some(result) = consume_item(player, item_name) {
         result
@@ -305,3 +327,37 @@ pub fn display_and_handle_inventory(
     // Return message
         "Viewed inventory.".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::StatusKind;
+
+    #[test]
+    fn cooking_turns_raw_food_into_cooked_food() {
+        let mut player = Player::new();
+        player.add_item_to_inventory(100017, 3); // Raw Beef
+        let cooked_shrimp = player.inventory[&100016];
+
+        let message = cook_raw_food(&mut player);
+
+        assert!(message.starts_with("Cooked"));
+        assert!(!player.inventory.contains_key(&100015));
+        assert!(!player.inventory.contains_key(&100017));
+        assert_eq!(player.inventory[&100016], cooked_shrimp + 2);
+        assert_eq!(player.inventory[&100018], 3);
+        assert_eq!(cook_raw_food(&mut player), "You have nothing to cook.");
+    }
+
+    #[test]
+    fn some_meals_leave_a_lasting_effect() {
+        let mut player = Player::new();
+        player.add_item_to_inventory(100018, 1); // Cooked Beef
+
+        let message = handle_eat_command(&mut player, "Cooked Beef");
+
+        assert!(message.contains("You feel Mighty for 5 turns."));
+        assert_eq!(player.status_effects.potency(StatusKind::Might), 3);
+        assert!(!player.inventory.contains_key(&100018));
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use rand::Rng;
 use std::fmt;
+use crate::status::{StatusEffect, StatusKind};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Item {
@@ -32,6 +33,8 @@ pub enum ItemType {
 pub struct Effect {
     pub health_change: i32,
     pub stamina_change: i32,
+    #[serde(default)]
+    pub status: Option<StatusEffect>, // Lasting effect of eating it
 }
 
 // Implement the Display trait for ItemType
@@ -246,6 +249,47 @@ pub fn create_items() -> HashMap<u32, Item> {
         },
     );
 
+    // Ores
+    items.insert(
+        100027,
+        Item {
+            id: 100027,
+            name: "Copper Ore".to_string(),
+            item_type: ItemType::CraftingMaterial,
+            weight: 2.0,
+            durability: None,
+            effect: None,
+            attack_bonus: None,
+            defense_bonus: None,
+        },
+    );
+    items.insert(
+        100028,
+        Item {
+            id: 100028,
+            name: "Tin Ore".to_string(),
+            item_type: ItemType::CraftingMaterial,
+            weight: 2.0,
+            durability: None,
+            effect: None,
+            attack_bonus: None,
+            defense_bonus: None,
+        },
+    );
+    items.insert(
+        100029,
+        Item {
+            id: 100029,
+            name: "Iron Ore".to_string(),
+            item_type: ItemType::CraftingMaterial,
+            weight: 2.0,
+            durability: None,
+            effect: None,
+            attack_bonus: None,
+            defense_bonus: None,
+        },
+    );
+
     // Consumables
     items.insert(
         100015,
@@ -258,6 +302,7 @@ pub fn create_items() -> HashMap<u32, Item> {
             effect: Some(Effect {
                 health_change: 5,
                 stamina_change: 0,
+                status: None,
             }),
             attack_bonus: None,
             defense_bonus: None,
@@ -274,6 +319,7 @@ pub fn create_items() -> HashMap<u32, Item> {
             effect: Some(Effect {
                 health_change: 10,
                 stamina_change: 5,
+                status: None,
             }),
             attack_bonus: None,
             defense_bonus: None,
@@ -292,6 +338,7 @@ pub fn create_items() -> HashMap<u32, Item> {
             effect: Some(Effect {
                 health_change: 8,
                 stamina_change: 0,
+                status: None,
             }),
             attack_bonus: None,
             defense_bonus: None,
@@ -308,6 +355,77 @@ pub fn create_items() -> HashMap<u32, Item> {
             effect: Some(Effect {
                 health_change: 20,
                 stamina_change: 10,
+                status: Some(StatusEffect::new(StatusKind::Might, 3, 5)),
+            }),
+            attack_bonus: None,
+            defense_bonus: None,
+        },
+    );
+
+    // Fish
+    items.insert(
+        100023,
+        Item {
+            id: 100023,
+            name: "Raw Trout".to_string(),
+            item_type: ItemType::Consumable,
+            weight: 0.4,
+            durability: None,
+            effect: Some(Effect {
+                health_change: 6,
+                stamina_change: 0,
+                status: None,
+            }),
+            attack_bonus: None,
+            defense_bonus: None,
+        },
+    );
+    items.insert(
+        100024,
+        Item {
+            id: 100024,
+            name: "Cooked Trout".to_string(),
+            item_type: ItemType::Consumable,
+            weight: 0.4,
+            durability: None,
+            effect: Some(Effect {
+                health_change: 15,
+                stamina_change: 5,
+                status: Some(StatusEffect::new(StatusKind::Guard, 3, 5)),
+            }),
+            attack_bonus: None,
+            defense_bonus: None,
+        },
+    );
+    items.insert(
+        100025,
+        Item {
+            id: 100025,
+            name: "Raw Salmon".to_string(),
+            item_type: ItemType::Consumable,
+            weight: 0.6,
+            durability: None,
+            effect: Some(Effect {
+                health_change: 8,
+                stamina_change: 0,
+                status: None,
+            }),
+            attack_bonus: None,
+            defense_bonus: None,
+        },
+    );
+    items.insert(
+        100026,
+        Item {
+            id: 100026,
+            name: "Cooked Salmon".to_string(),
+            item_type: ItemType::Consumable,
+            weight: 0.6,
+            durability: None,
+            effect: Some(Effect {
+                health_change: 22,
+                stamina_change: 8,
+                status: Some(StatusEffect::new(StatusKind::Regen, 2, 5)),
             }),
             attack_bonus: None,
             defense_bonus: None,
@@ -326,6 +444,7 @@ pub fn create_items() -> HashMap<u32, Item> {
             effect: Some(Effect {
                 health_change: 4,
                 stamina_change: 2,
+                status: None,
             }),
             attack_bonus: None,
             defense_bonus: None,
@@ -356,13 +475,30 @@ pub fn get_starting_items() -> HashMap<u32, u32> {
     starting_items
 }
 
+// Raw food that can be cooked on a campfire, and what it turns into
+pub fn cooked_version(raw_item_id: u32) -> Option<u32> {
+    match raw_item_id {
+        100015 => Some(100016), // Raw Shrimp -> Cooked Shrimp
+        100017 => Some(100018), // Raw Beef -> Cooked Beef
+        100023 => Some(100024), // Raw Trout -> Cooked Trout
+        100025 => Some(100026), // Raw Salmon -> Cooked Salmon
+        _ => None,
+    }
+}
+
+// (Item ID, Optional Quantity Range, Weight)
+pub type LootEntry = (u32, Option<(u32, u32)>, f32);
+
 // Basic Loot Table Struct
 #[derive(Debug, Clone)]
 pub struct LootTable {
-    pub items: Vec<(u32, Option<(u32, u32)>, f32)>, // (Item ID, Optional Quantity Range, Weight)
+    pub items: Vec<LootEntry>,
 }
 
 // Create basic loot tables using weight for item drop probability
+/// Loot table of the chests found in dungeons.
+pub const CHEST_LOOT_TABLE: &str = "chest";
+
 pub fn create_loot_tables() -> HashMap<String, LootTable> {
     let mut loot_tables = HashMap::new();
 
@@ -379,12 +515,30 @@ pub fn create_loot_tables() -> HashMap<String, LootTable> {
         },
     );
 
+    loot_tables.insert(
+        CHEST_LOOT_TABLE.to_string(),
+        LootTable {
+            items: vec![
+                (100001, Some((5, 20)), 60.0), // 5-20 Gold Coins [Weight: 60]
+                (100004, Some((1, 1)), 15.0),  // Bronze Dagger [Weight: 15]
+                (100018, Some((1, 3)), 30.0),  // Cooked Beef [Weight: 30]
+                (100026, Some((1, 2)), 15.0),  // Cooked Salmon [Weight: 15]
+                (100029, Some((1, 4)), 20.0),  // Iron Ore [Weight: 20]
+                (100009, Some((1, 1)), 10.0),  // Leather Boots [Weight: 10]
+            ],
+        },
+    );
+
     loot_tables
 }
 
 // Function to calculate loot using weight-based approach
 pub fn calculate_loot(loot_table: &LootTable) -> HashMap<u32, u32> {
-    let mut rng = rand::thread_rng();
+    roll_loot(loot_table, &mut rand::thread_rng())
+}
+
+/// Like `calculate_loot`, but rolls with `rng` so a seeded fight drops the same loot.
+pub fn roll_loot(loot_table: &LootTable, rng: &mut impl Rng) -> HashMap<u32, u32> {
     let mut loot_result = HashMap::new();
 
     let total_weight: f32 = loot_table.items.iter().map(|(_, _, weight)| weight).sum();
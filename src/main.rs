@@ -1,50 +1,50 @@
+mod archive;
+mod autosave;
 mod combat;
+mod damage;
+mod dungeon;
 mod enemy;
+mod gathering;
 mod inventory;
 mod items;
 mod map;
+mod paths;
 mod player;
 mod quest;
+mod save;
 mod skill;
+mod status;
+mod terrain;
 mod utils;
-
-use crate::combat::handle_combat;
-use crate::inventory::display_and_handle_inventory;
+mod world;
+
+use crate::archive::{export_save, free_save_name, import_save, read_archive, ARCHIVE_EXTENSION};
+use crate::autosave::{AutosaveConfig, AutosaveTracker};
+use crate::combat::{handle_combat, CombatOutcome};
+use crate::dungeon::{floor_enemies, floor_of, open_chest};
+use crate::gathering::gather;
+use crate::inventory::{cook_raw_food, display_and_handle_inventory};
 use crate::items::create_loot_tables;
-use crate::map::Tile;
-use crate::player::Player;
-use crate::quest::{sample_quests, starting_quest, Quest};
+use crate::paths::{copy_dir, data_dir, data_dir_flag, migrate_legacy_saves, SAVES_FOLDER};
+use crate::player::{Player, RespawnPoint};
+use crate::quest::starting_quest;
+use crate::save::{
+    list_backups, read_backup, read_metadata, read_save, read_world, restore_backup, save_game,
+    save_world, SaveError, SaveMetadata,
+};
+use crate::status::describe_tick;
 use crate::utils::{faf, should_encounter_enemy};
+use crate::world::Areas;
 use chrono::{DateTime, Local};
-use enemy::basic_enemies;
-use map::{Direction, Map};
-use rand::Rng;
+use enemy::{basic_enemies, encounter_table, Enemy};
+use map::{parse_seed, Direction, Entity, Map, Tile, WORLD_MAP_SCALE, WORLD_SIZE};
 use regex::Regex;
-use serde::{Deserialize, Serialize};
-use serde_json;
 use skill::initialize_skills;
 use std::collections::VecDeque;
 use std::fs::{self, create_dir_all};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use term_size;
-
-#[derive(Serialize, Deserialize, Clone)]
-struct CharacterSave {
-    player: Player,
-    game_map: Map,
-    quests: Vec<Quest>,
-    character_name: String,
-    name: String,
-    health: i32,
-    level: i32,
-    experience: i32,
-    skills: Vec<(String, i32, f32)>,
-    player_x: usize,
-    player_y: usize,
-    current_map: String,
-    inventory: std::collections::HashMap<u32, u32>,
-}
+use std::time::{Duration, Instant, SystemTime};
 
 // ====================//
 // Game Initialization //
@@ -53,9 +53,29 @@ struct CharacterSave {
 fn main() {
     let version = option_env!("VERSION").unwrap_or("unknown version");
     let build_number = option_env!("BUILD_NUMBER").unwrap_or("unknown build");
-    let saves_path = Path::new("Saves");
+    let data_dir_flag = match data_dir_flag(std::env::args().skip(1)) {
+        Ok(flag) => flag,
+        Err(e) => {
+            eprintln!("{}\nUsage: rustpg [--data-dir <path>]", e);
+            std::process::exit(2);
+        }
+    };
+    let data_dir = data_dir(data_dir_flag);
+    let saves_path = data_dir.join(SAVES_FOLDER);
+    match migrate_legacy_saves(Path::new(SAVES_FOLDER), &saves_path) {
+        Ok(true) => {
+            println!("Moved your saves to {}.", saves_path.display());
+            println!("Press Enter to continue...");
+            let _ = io::stdin().read_line(&mut String::new());
+        }
+        Ok(false) => {}
+        Err(e) => report_save_error(
+            &format!("Failed to move ./{} to {}", SAVES_FOLDER, saves_path.display()),
+            &SaveError::Io(e),
+        ),
+    }
     if !saves_path.exists() {
-        fs::create_dir(saves_path).expect("Failed to create Saves folder");
+        create_dir_all(&saves_path).expect("Failed to create Saves folder");
     }
 
     loop {
@@ -78,20 +98,28 @@ fn main() {
         let choice = choice.trim();
 
         match choice {
-            "1" => new_game(),
-            "2" => {
-                if let Some(recent_save) = get_recent_save() {
-                    load_game(&recent_save);
-                } else {
+            "1" => new_game(&saves_path),
+            "2" => match get_recent_save(&saves_path) {
+                Ok(Some(recent_save)) => {
+                    if let Err(e) = load_game(&recent_save) {
+                        report_save_error("Failed to load save", &e);
+                    }
+                }
+                Ok(None) => {
                     println!("No recent save found. Press Enter to continue...");
                     let _ = io::stdin().read_line(&mut String::new());
                 }
-            }
-            "3" => {
-                if let Some(save) = load_save_menu() {
-                    load_game(&save);
+                Err(e) => report_save_error("Failed to read saves", &e),
+            },
+            "3" => match load_save_menu(&saves_path) {
+                Ok(Some(save)) => {
+                    if let Err(e) = load_game(&save) {
+                        report_save_error("Failed to load save", &e);
+                    }
                 }
-            }
+                Ok(None) => {}
+                Err(e) => report_save_error("Failed to read saves", &e),
+            },
             "q" => std::process::exit(0),
             _ => {
                 println!("Invalid choice. Please try again. Press Enter to continue...");
@@ -101,7 +129,7 @@ fn main() {
     }
 }
 
-fn new_game() {
+fn new_game(saves_path: &Path) {
     loop {
         print!("\x1B[2J\x1B[1;1H");
         io::stdout().flush().unwrap();
@@ -135,28 +163,34 @@ fn new_game() {
             let _ = io::stdin().read_line(&mut String::new());
             continue;
         }
-        let sanitized_name = if character_name.starts_with('*') {
-            character_name[1..].to_string()
-        } else {
-            character_name.to_string()
-        };
+        let sanitized_name = character_name
+            .strip_prefix('*')
+            .unwrap_or(character_name)
+            .to_string();
         // Proceed with creating the game
-        let save_folder = Path::new("Saves").join(&sanitized_name);
-        create_dir_all(&save_folder).expect("Failed to create save directory");
+        let save_folder = saves_path.join(&sanitized_name);
+        if let Err(e) = create_dir_all(&save_folder) {
+            report_save_error("Failed to create save directory", &SaveError::Io(e));
+            continue;
+        }
+        println!("Enter a world seed to share a world, or press Enter for a random one:");
+        let mut seed = String::new();
+        io::stdin()
+            .read_line(&mut seed)
+            .expect("Failed to read line");
         let mut player = Player::new();
-        let mut game_map = Map::new(300, 300);
+        let game_map = Map::new(WORLD_SIZE, WORLD_SIZE, parse_seed(&seed));
         player.skills = initialize_skills();
         let quest = starting_quest();
         player.add_quest(quest.clone());
-        let quests = sample_quests();
-        game_map.campfire_x = game_map.player_x;
-        game_map.campfire_y = game_map.player_y + 1;
-        game_map.set_tile(game_map.campfire_x, game_map.campfire_y, Tile::Campfire);
-        save_game(&player, &game_map, &save_folder, &sanitized_name);
+        if let Err(e) = save_game(&player, &game_map, &save_folder, &sanitized_name) {
+            report_save_error("Failed to create save", &e);
+            continue;
+        }
         game_loop(
             player,
             game_map,
-            quests,
+            Areas::default(),
             save_folder.to_path_buf(),
             sanitized_name,
         );
@@ -170,29 +204,44 @@ fn sanitize_character_name(name: &str) -> String {
     space_reduced_name.to_string()
 }
 
-fn get_recent_save() -> Option<PathBuf> {
-    let saves_path = Path::new("Saves");
-    let mut save_dirs: Vec<_> = fs::read_dir(saves_path)
-        .expect("Failed to read Saves directory")
+fn report_save_error(context: &str, error: &SaveError) {
+    println!("{}: {}", context, error);
+    println!("Press Enter to continue...");
+    let _ = io::stdin().read_line(&mut String::new());
+}
+
+/// Lists every save folder, oldest first.
+fn list_save_dirs(saves_path: &Path) -> Result<Vec<PathBuf>, SaveError> {
+    let mut save_dirs: Vec<(SystemTime, PathBuf)> = fs::read_dir(saves_path)?
         .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.file_type().unwrap().is_dir())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        // Skip half-finished imports
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| {
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (modified, entry.path())
+        })
         .collect();
 
-    save_dirs.sort_by_key(|entry| fs::metadata(entry.path()).unwrap().modified().unwrap());
-    save_dirs.last().map(|entry| entry.path())
+    save_dirs.sort_by_key(|(modified, _)| *modified);
+    Ok(save_dirs.into_iter().map(|(_, path)| path).collect())
 }
 
-fn load_save_menu() -> Option<PathBuf> {
-    let saves_path = Path::new("Saves");
-    let mut save_dirs: Vec<_> = fs::read_dir(saves_path)
-        .expect("Failed to read Saves directory")
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.file_type().unwrap().is_dir())
-        .collect();
+fn get_recent_save(saves_path: &Path) -> Result<Option<PathBuf>, SaveError> {
+    Ok(list_save_dirs(saves_path)?.pop())
+}
 
-    save_dirs.sort_by_key(|entry| fs::metadata(entry.path()).unwrap().modified().unwrap());
+fn load_save_menu(saves_path: &Path) -> Result<Option<PathBuf>, SaveError> {
+    let mut save_dirs = list_save_dirs(saves_path)?;
     save_dirs.reverse();
 
+    // Only the small metadata header is read here; broken saves are flagged
+    let save_metadata: Vec<Result<SaveMetadata, SaveError>> =
+        save_dirs.iter().map(|path| read_metadata(path)).collect();
+
     print!("\x1B[2J\x1B[1;1H");
     io::stdout().flush().unwrap();
 
@@ -204,33 +253,48 @@ fn load_save_menu() -> Option<PathBuf> {
 
     if save_dirs.is_empty() {
         println!("No saves found.");
-        println!("\n(b)ack, (q)uit");
-        print!("\nSelect a save to load: ");
+        println!("\n(imp)ort <file>, (b)ack, (q)uit");
+        print!("\nEnter your choice: ");
         io::stdout().flush().unwrap();
-        return None;
-    }
-
-    println!("Select a save to load:");
-    for (i, entry) in save_dirs.iter().enumerate() {
-        let save_name = entry.file_name().into_string().unwrap();
-        let metadata = fs::metadata(entry.path()).unwrap();
-        let modified: DateTime<Local> = DateTime::from(metadata.modified().unwrap());
-
-        let level = get_player_level(&entry.path()).unwrap_or(1);
+    } else {
+        println!("Select a save to load:");
+        for (i, path) in save_dirs.iter().enumerate() {
+            let save_name = save_display_name(path);
+            let modified: DateTime<Local> = fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+                .into();
+
+            match &save_metadata[i] {
+                Ok(metadata) => println!(
+                    "{}. {} | lvl {} | cb {} | {} | {} | {} quests | {} | v{} ({})",
+                    i + 1,
+                    save_name,
+                    metadata.total_level,
+                    metadata.combat_level,
+                    metadata.play_time_display(),
+                    metadata.last_location,
+                    metadata.completed_quests,
+                    modified.format("%b, %d %Y"),
+                    metadata.game_version,
+                    metadata.build
+                ),
+                Err(_) => println!(
+                    "{}. {} | corrupt | {}",
+                    i + 1,
+                    save_name,
+                    modified.format("%b, %d %Y")
+                ),
+            }
+        }
 
         println!(
-            "{}. {} | lvl {} | {}",
-            i + 1,
-            save_name,
-            level,
-            modified.format("%b, %d %Y")
+            "\n(dup)licate, (del)ete, (res)tore backup, (exp)ort, (imp)ort <file>, (b)ack, (q)uit"
         );
+        print!("\nSelect a save to load: ");
+        io::stdout().flush().unwrap();
     }
 
-    println!("\n(dup)licate, (del)ete, (b)ack, (q)uit");
-    print!("\nSelect a save to load: ");
-    io::stdout().flush().unwrap();
-
     let mut choice = String::new();
     io::stdin()
         .read_line(&mut choice)
@@ -240,17 +304,12 @@ fn load_save_menu() -> Option<PathBuf> {
     if choice == "q" {
         std::process::exit(0);
     } else if choice == "b" {
-        return None;
+        return Ok(None);
     }
 
     if let Some(name) = choice.strip_prefix("del ") {
         let target_name = name.trim();
-        if let Some(target_save) = save_dirs.iter().find(|entry| {
-            entry
-                .file_name()
-                .to_string_lossy()
-                .eq_ignore_ascii_case(target_name)
-        }) {
+        if let Some(target_save) = find_save(&save_dirs, target_name) {
             println!(
                 "Are you sure you want to delete the save for '{}'?\n(type 'yes' to confirm):",
                 target_name
@@ -260,7 +319,7 @@ fn load_save_menu() -> Option<PathBuf> {
                 .read_line(&mut confirm)
                 .expect("Failed to read line");
             if confirm.trim().eq_ignore_ascii_case("yes") {
-                if let Err(e) = fs::remove_dir_all(target_save.path()) {
+                if let Err(e) = fs::remove_dir_all(target_save) {
                     println!("Failed to delete save: {}", e);
                 } else {
                     println!("Save for '{}' has been deleted successfully.", target_name);
@@ -273,17 +332,44 @@ fn load_save_menu() -> Option<PathBuf> {
         }
         println!("Press Enter to continue...");
         let _ = io::stdin().read_line(&mut String::new());
-        return load_save_menu();
+        return load_save_menu(saves_path);
+    }
+
+    if let Some(name) = choice.strip_prefix("res ") {
+        let target_name = name.trim();
+        if let Some(target_save) = find_save(&save_dirs, target_name) {
+            restore_backup_menu(target_save);
+        } else {
+            println!("Save for '{}' not found.", target_name);
+            println!("Press Enter to continue...");
+            let _ = io::stdin().read_line(&mut String::new());
+        }
+        return load_save_menu(saves_path);
+    }
+
+    if let Some(name) = choice.strip_prefix("exp ") {
+        let target_name = name.trim();
+        if let Some(target_save) = find_save(&save_dirs, target_name) {
+            export_save_menu(target_save);
+        } else {
+            println!("Save for '{}' not found.", target_name);
+        }
+        println!("Press Enter to continue...");
+        let _ = io::stdin().read_line(&mut String::new());
+        return load_save_menu(saves_path);
+    }
+
+    if let Some(file) = choice.strip_prefix("imp ") {
+        let file = file.trim().trim_matches(|c| c == '"' || c == '\'');
+        import_save_menu(saves_path, Path::new(file));
+        println!("Press Enter to continue...");
+        let _ = io::stdin().read_line(&mut String::new());
+        return load_save_menu(saves_path);
     }
 
     if let Some(name) = choice.strip_prefix("dup ") {
         let target_name = name.trim();
-        if let Some(old_save) = save_dirs.iter().find(|entry| {
-            entry
-                .file_name()
-                .to_string_lossy()
-                .eq_ignore_ascii_case(target_name)
-        }) {
+        if let Some(old_save) = find_save(&save_dirs, target_name) {
             println!("Enter a name for the duplicated save:");
             let mut new_name = String::new();
             io::stdin()
@@ -298,7 +384,7 @@ fn load_save_menu() -> Option<PathBuf> {
                 );
             } else if let Err(e) = create_dir_all(&new_path) {
                 println!("Failed to create duplicate save directory: {}", e);
-            } else if let Err(e) = copy_save_folder(&old_save.path(), &new_path) {
+            } else if let Err(e) = copy_dir(old_save, &new_path) {
                 println!("Failed to copy save directory: {}", e);
             } else {
                 println!(
@@ -308,154 +394,251 @@ fn load_save_menu() -> Option<PathBuf> {
             }
             println!("Press Enter to continue...");
             let _ = io::stdin().read_line(&mut String::new());
-            return load_save_menu();
+            return load_save_menu(saves_path);
         } else {
             println!("Save for '{}' not found.", target_name);
             println!("Press Enter to continue...");
             let _ = io::stdin().read_line(&mut String::new());
-            return load_save_menu();
+            return load_save_menu(saves_path);
         }
     }
 
     if let Ok(index) = choice.parse::<usize>() {
         if index > 0 && index <= save_dirs.len() {
-            return Some(
-                save_dirs[index - 1]
-                    .path()
-                    .to_str()
-                    .unwrap()
-                    .to_string()
-                    .into(),
-            );
+            let save_path = &save_dirs[index - 1];
+            if let Err(e) = read_save(save_path) {
+                handle_corrupt_save(save_path, &e);
+                return load_save_menu(saves_path);
+            }
+            return Ok(Some(save_path.clone()));
         }
     }
 
     println!("Invalid choice. Press Enter to continue...");
     let _ = io::stdin().read_line(&mut String::new());
-    load_save_menu()
+    load_save_menu(saves_path)
 }
 
-fn get_player_level(save_path: &Path) -> Option<u32> {
-    // This function would load save data and return the player's level
-    // Placeholder for now - real implementation needed
-    Some(1) // Example: default level 1
+fn save_display_name(save_path: &Path) -> String {
+    save_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
 }
 
-fn copy_save_folder(from: &Path, to: &Path) -> io::Result<()> {
-    for entry in fs::read_dir(from)? {
-        let entry = entry?;
-        let to_path = to.join(entry.file_name());
-        if entry.file_type()?.is_dir() {
-            create_dir_all(&to_path)?;
-            copy_save_folder(&entry.path(), &to_path)?;
-        } else {
-            fs::copy(entry.path(), to_path)?;
+fn find_save<'a>(save_dirs: &'a [PathBuf], name: &str) -> Option<&'a PathBuf> {
+    save_dirs
+        .iter()
+        .find(|path| save_display_name(path).eq_ignore_ascii_case(name))
+}
+
+/// Offers to skip, restore or delete a save that failed to load.
+fn handle_corrupt_save(save_path: &Path, error: &SaveError) {
+    let save_name = save_display_name(save_path);
+    println!("\nThe save '{}' is corrupt: {}", save_name, error);
+    if !list_backups(save_path).is_empty() {
+        println!("(s)kip, (r)estore from backup, (del)ete");
+    } else {
+        println!("(s)kip, (del)ete");
+    }
+    print!("\nEnter your choice: ");
+    io::stdout().flush().unwrap();
+
+    let mut choice = String::new();
+    io::stdin()
+        .read_line(&mut choice)
+        .expect("Failed to read line");
+
+    match choice.trim() {
+        "r" if !list_backups(save_path).is_empty() => {
+            restore_backup_menu(save_path);
+            return;
         }
+        "del" => {
+            println!(
+                "Are you sure you want to delete the save for '{}'?\n(type 'yes' to confirm):",
+                save_name
+            );
+            let mut confirm = String::new();
+            io::stdin()
+                .read_line(&mut confirm)
+                .expect("Failed to read line");
+            if confirm.trim().eq_ignore_ascii_case("yes") {
+                if let Err(e) = fs::remove_dir_all(save_path) {
+                    println!("Failed to delete save: {}", e);
+                } else {
+                    println!("Save for '{}' has been deleted successfully.", save_name);
+                }
+            } else {
+                println!("Delete action cancelled.");
+            }
+        }
+        _ => println!("Skipped '{}'.", save_name),
     }
-    Ok(())
+    println!("Press Enter to continue...");
+    let _ = io::stdin().read_line(&mut String::new());
 }
 
-fn load_game(save_folder: &Path) {
-    // Deserialize the saved character data
-    let character_file_path = save_folder.join("character.json");
-    let character_data: CharacterSave = serde_json::from_str(
-        &fs::read_to_string(&character_file_path).expect("Failed to read character file"),
-    )
-    .expect("Failed to parse character file");
-
-    // Deserialize the map data with player coordinates
-    let map_file_path = save_folder.join("map.txt");
-    let map_data_str = fs::read_to_string(&map_file_path).expect("Failed to read map file");
-
-    // Ensure that `player_x` and `player_y` are correctly retrieved from `character_data`
-    let mut map_data = Map::deserialize_map(
-        character_data.game_map.width,
-        character_data.game_map.height,
-        &map_data_str,
-        character_data.player_x,
-        character_data.player_y,
-    );
+/// Lists the rotating backups of a save and restores the one picked.
+fn restore_backup_menu(save_path: &Path) {
+    let save_name = save_display_name(save_path);
+    let backups = list_backups(save_path);
+    if backups.is_empty() {
+        println!("No backups found for '{}'.", save_name);
+    } else {
+        println!("\nBackups for '{}':", save_name);
+        for number in &backups {
+            let backup_file = save_path.join(format!("character.json.{}", number));
+            let modified: DateTime<Local> = fs::metadata(backup_file)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+                .into();
+            let state = if read_backup(save_path, *number).is_ok() {
+                "ok"
+            } else {
+                "corrupt"
+            };
+            println!(
+                "{}. {} | {}",
+                number,
+                modified.format("%b, %d %Y %H:%M"),
+                state
+            );
+        }
+        print!("\nSelect a backup to restore (b)ack: ");
+        io::stdout().flush().unwrap();
+
+        let mut choice = String::new();
+        io::stdin()
+            .read_line(&mut choice)
+            .expect("Failed to read line");
+        match choice.trim().parse::<usize>() {
+            Ok(number) if backups.contains(&number) => match restore_backup(save_path, number) {
+                Ok(()) => println!(
+                    "Save for '{}' has been restored from backup {}.",
+                    save_name, number
+                ),
+                Err(e) => println!("Failed to restore backup: {}", e),
+            },
+            _ => println!("Restore action cancelled."),
+        }
+    }
+    println!("Press Enter to continue...");
+    let _ = io::stdin().read_line(&mut String::new());
+}
 
-    // Initialize the player and set their position
-    let mut player = Player::new();
-    player.set_position(character_data.player_x, character_data.player_y);
+/// Packs a save into a single archive file that can be copied to another
+/// machine or attached to a bug report.
+fn export_save_menu(save_path: &Path) {
+    let save_name = save_display_name(save_path);
+    let default_file = format!("{}.{}", save_name, ARCHIVE_EXTENSION);
+    println!("Export to (Enter for '{}'):", default_file);
+    let mut file = String::new();
+    io::stdin()
+        .read_line(&mut file)
+        .expect("Failed to read line");
+    let file = match file.trim() {
+        "" => default_file,
+        file => file.to_string(),
+    };
 
-    // Clear any existing player positions to avoid duplicates
-    map_data.clear_player_positions();
+    match export_save(save_path, Path::new(&file)) {
+        Ok(_) => println!("Save for '{}' has been exported to '{}'.", save_name, file),
+        Err(e) => println!("Failed to export save: {}", e),
+    }
+}
 
-    // Set the player's position on the map
-    map_data.set_tile(
-        character_data.player_x,
-        character_data.player_y,
-        Tile::Player,
-    );
+/// Unpacks an exported save, asking what to do when the name is taken.
+fn import_save_menu(saves_path: &Path, file: &Path) {
+    let archive = match read_archive(file) {
+        Ok(archive) => archive,
+        Err(e) => {
+            println!("Failed to import '{}': {}", file.display(), e);
+            return;
+        }
+    };
+
+    let mut save_name = archive.character_name().to_string();
+    let mut overwrite = false;
+    if saves_path.join(&save_name).exists() {
+        let free_name = free_save_name(saves_path, &save_name);
+        println!(
+            "A save named '{}' already exists. (r)ename to '{}', (o)verwrite, (c)ancel",
+            save_name, free_name
+        );
+        let mut choice = String::new();
+        io::stdin()
+            .read_line(&mut choice)
+            .expect("Failed to read line");
+        match choice.trim() {
+            "r" => save_name = free_name,
+            "o" => {
+                println!(
+                    "This will replace the existing save and its backups.\n(type 'yes' to confirm):"
+                );
+                let mut confirm = String::new();
+                io::stdin()
+                    .read_line(&mut confirm)
+                    .expect("Failed to read line");
+                if !confirm.trim().eq_ignore_ascii_case("yes") {
+                    println!("Import cancelled.");
+                    return;
+                }
+                overwrite = true;
+            }
+            _ => {
+                println!("Import cancelled.");
+                return;
+            }
+        }
+    }
+
+    match import_save(&archive, saves_path, &save_name, overwrite) {
+        Ok(_) => println!("Save for '{}' has been imported successfully.", save_name),
+        Err(e) => println!("Failed to import '{}': {}", file.display(), e),
+    }
+}
+
+fn load_game(save_folder: &Path) -> Result<(), SaveError> {
+    let (player, map_data, areas, character_name) = read_world(save_folder)?;
 
     println!(
         "Loaded Player Position: ({}, {})",
-        character_data.player_x, character_data.player_y
+        map_data.player_x, map_data.player_y
     );
 
-    // Load quests and other data as needed
-    let quests = sample_quests();
-
-    // Start the game loop with the updated player and map
+    // Start the game loop with the restored player and map
     game_loop(
         player,
         map_data,
-        quests,
+        areas,
         save_folder.to_path_buf(),
-        character_data.character_name,
+        character_name,
     );
+    Ok(())
 }
 
-fn save_game(player: &Player, game_map: &Map, save_folder: &Path, character_name: &str) {
-    let character_save = CharacterSave {
-        player: player.clone(),
-        game_map: game_map.clone(),
-        quests: player.quests.clone(),
-        character_name: character_name.to_string(),
-        name: character_name.to_string(),
-        health: player.health,
-        level: player.level,
-        experience: player.experience,
-        skills: player
-            .skills
-            .iter()
-            .map(|(name, skill)| (name.clone(), skill.level, skill.experience as f32))
-            .collect(),
-        player_x: game_map.player_x,
-        player_y: game_map.player_y,
-        current_map: save_folder.join("map.txt").to_string_lossy().into_owned(),
-        inventory: player.inventory.clone(),
-    };
-
-    let character_save_path = save_folder.join("character.json");
-    fs::write(
-        &character_save_path,
-        serde_json::to_string(&character_save).unwrap(),
-    )
-    .expect("Failed to write character file");
-
-    let map_save_path = save_folder.join("map.txt");
-    let serialized_map = game_map.serialize_map();
-    fs::write(&map_save_path, serialized_map).expect("Failed to write map file");
-}
+/// Squares of the corner minimap, each `WORLD_MAP_SCALE` tiles across.
+const MINIMAP_COLUMNS: usize = 15;
+const MINIMAP_ROWS: usize = 7;
 
 fn game_loop(
     mut player: Player,
     mut game_map: Map,
-    quests: Vec<Quest>,
+    mut areas: Areas,
     save_folder: PathBuf,
     character_name: String,
 ) {
     let mut recent_actions: VecDeque<String> = VecDeque::new();
     let mut new_action: String = String::new();
+    let mut session_start = Instant::now();
+    let mut autosave = AutosaveTracker::new(AutosaveConfig::from_env(), &player);
 
     // Determine view size based on terminal height
     let view_size = if let Some((_, height)) = term_size::dimensions() {
-        if (height >= 44) {
+        if height >= 44 {
             30
-        } else if (height >= 29) {
+        } else if height >= 29 {
             20
         } else {
             10
@@ -464,6 +647,7 @@ fn game_loop(
         30
     };
     game_map.view_radius = view_size / 2;
+    let mut show_minimap = false;
 
     loop {
         // Clear the terminal
@@ -478,23 +662,50 @@ fn game_loop(
         let map_lines: Vec<&str> = map_str.lines().collect();
         let map_height = map_lines.len();
 
-        // Calculate the maximum number of recent actions based on map height
-        let max_recent_actions = if map_height > 1 {
-            map_height - 1
+        // The minimap takes the top right corner, above the recent actions
+        let minimap = if show_minimap {
+            game_map.render_world_map(
+                game_map.player_x,
+                game_map.player_y,
+                MINIMAP_COLUMNS,
+                MINIMAP_ROWS,
+            )
         } else {
-            0
+            String::new()
         };
+        let minimap_height = if show_minimap { MINIMAP_ROWS + 1 } else { 0 };
+
+        // Calculate the maximum number of recent actions based on map height
+        let max_recent_actions = map_height.saturating_sub(1 + minimap_height);
+
+        // Autosave through the regular save path when a trigger has fired
+        if let Some(reason) = autosave.check(&player) {
+            record_play_time(&mut player, &mut session_start);
+            let saved = save_world(&player, &game_map, &areas, &save_folder, &character_name);
+            let action = match saved {
+                Ok(()) => format!("Autosaved ({}).", reason),
+                Err(e) => format!("Autosave failed: {}", e),
+            };
+            push_recent_action(&mut recent_actions, action, max_recent_actions);
+        }
 
         // Prepare menu lines
-        let mut menu_lines = Vec::new();
-        menu_lines.push("(w/a/s/d) move | (status) player status | (quests) view quests");
-        menu_lines.push("(i) inventory | (m) menu | (q) quit");
+        let menu_lines = [
+            "(w/a/s/d) move | (status) player status | (quests) view quests",
+            "(i) inventory | (e) interact | (map) world map | (minimap) toggle minimap",
+            "(m) menu | (q) quit",
+        ];
 
         // Prepare recent actions lines
         let mut info_lines = Vec::new();
 
         // If not in combat, add recent actions
         if !player.in_combat {
+            if show_minimap {
+                info_lines.extend(minimap.lines());
+                info_lines.resize(MINIMAP_ROWS, "");
+                info_lines.push("");
+            }
             info_lines.push("Recent Actions:");
             // Get the last `max_recent_actions` actions
             let actions_to_display: Vec<&String> = recent_actions.iter().rev().take(max_recent_actions).collect();
@@ -503,16 +714,17 @@ fn game_loop(
                 info_lines.push(action);
             }
             // Pad with "----------" to ensure exactly `max_recent_actions` lines
-            for _ in actions_to_display.len()..max_recent_actions {
-                info_lines.push("----------");
-            }
+            info_lines.resize(
+                info_lines.len() + max_recent_actions.saturating_sub(actions_to_display.len()),
+                "----------",
+            );
         }
 
         // Determine the maximum number of lines between map and info
         let max_lines = map_lines.len().max(info_lines.len());
 
         // Calculate the width of the map for alignment
-        let map_width = map_lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let map_width = map_lines.iter().map(|line| visible_width(line)).max().unwrap_or(0);
 
         // Print the menu at the top
         for menu_line in &menu_lines {
@@ -532,7 +744,9 @@ fn game_loop(
             } else {
                 ""
             };
-            println!("{:<width$}{}{}", map_part, SEPARATOR, info_part, width = map_width);
+            // Dimmed tiles carry escape codes, so pad by what is visible
+            let padding = " ".repeat(map_width - visible_width(map_part));
+            println!("{}{}{}{}", map_part, padding, SEPARATOR, info_part);
         }
 
         // Print the prompt below the map and recent actions
@@ -548,28 +762,22 @@ fn game_loop(
 
         if input == "faf" && !player.in_combat {
             println!("Initiating automatic movement...");
-            if faf(&mut player, &mut game_map) {
+            if faf(&mut player, &mut game_map, &mut autosave) {
                 // Enemy encountered during automatic movement
                 new_action = "Enemy encountered during automatic movement.".to_string();
                 recent_actions.push_back(new_action.clone());
 
                 // Proceed to handle combat
-                let mut rng = rand::thread_rng();
-                let enemies = basic_enemies();
-                let enemy = enemies[rng.gen_range(0..enemies.len())].clone();
-                let loot_tables = create_loot_tables();
-
-                let combat_result = handle_combat(&mut player, enemy, &loot_tables);
-                println!("{}", combat_result);
-                player.in_combat = false;
-
-                if player.health <= 0 {
-                    println!("You have been defeated!");
-                    println!("Press Enter to respawn...");
-                    let _ = io::stdin().read_line(&mut String::new());
-                    player.respawn(&mut game_map);
+                let enemies = random_encounter(&game_map);
+                let outcome = fight(&mut player, &mut game_map, &mut areas, enemies, &mut autosave);
+                if outcome == CombatOutcome::Defeat {
                     new_action = "Player has respawned.".to_string();
                 }
+            } else if player.health <= 0 {
+                // Poison or bleeding wore the player down on the way
+                player.respawn(&mut game_map, &mut areas);
+                new_action = "Player has respawned.".to_string();
+                recent_actions.push_back(new_action.clone());
             } else {
                 new_action = "Automatic movement completed.".to_string();
                 recent_actions.push_back(new_action.clone());
@@ -579,11 +787,28 @@ fn game_loop(
 
         match input {
             "q" => {
-                save_game(&player, &game_map, &save_folder, &character_name);
-                break; // Exit game
+                record_play_time(&mut player, &mut session_start);
+                match save_world(&player, &game_map, &areas, &save_folder, &character_name) {
+                    Ok(()) => {
+                        autosave.reset(&player);
+                        break; // Exit game
+                    }
+                    Err(e) => {
+                        println!("Failed to save game: {}", e);
+                        println!("Quit without saving? (y/n)");
+                        let mut confirm = String::new();
+                        io::stdin()
+                            .read_line(&mut confirm)
+                            .expect("Failed to read line");
+                        if confirm.trim().eq_ignore_ascii_case("y") {
+                            break;
+                        }
+                        new_action = "Failed to save game.".to_string();
+                    }
+                }
             }
             "w" | "s" | "a" | "d" => {
-                if (!player.in_combat) {
+                if !player.in_combat {
                     let direction = match input {
                         "w" => Direction::Up,
                         "s" => Direction::Down,
@@ -592,37 +817,117 @@ fn game_loop(
                         _ => unreachable!(),
                     };
                     player.facing = direction; // Update facing direction
-                    game_map.move_player(&direction);
-                    new_action = format!("Player moved {:?}", direction);
-
-                    // Random enemy encounter logic
-                    if should_encounter_enemy(1) {
-                        // 1% chance
-                        // Enemy encounter logic
-                        let mut rng = rand::thread_rng();
-                        let enemies = basic_enemies();
-                        let enemy = enemies[rng.gen_range(0..enemies.len())].clone();
-                        let loot_tables = create_loot_tables();
-                        player.in_combat = true; // Set in_combat to true before starting combat
-
-                        let combat_result = handle_combat(&mut player, enemy, &loot_tables);
-                        println!("{}", combat_result);
-
-                        player.in_combat = false; // Set in_combat to false after combat ends
-
-                        // After combat ends, check if player is dead
-                        if player.health <= 0 {
-                            println!("You have been defeated!");
-                            println!("Press Enter to respawn...");
-                            let _ = io::stdin().read_line(&mut String::new());
-                            player.respawn(&mut game_map);
-                            new_action = "Player has respawned.".to_string();
+
+                    // Doors and stairs with a portal lead to another map, other
+                    // doors open, and guards in the way have to be fought
+                    match game_map.faced_tile(direction) {
+                        Some((x, y, tile)) if game_map.portal_at(x, y).is_some() => {
+                            let way = match tile {
+                                Tile::StairsUp => "You climb the stairs",
+                                Tile::StairsDown => "You go down the stairs",
+                                _ => "You go through the door",
+                            };
+                            new_action = match areas.travel(&mut game_map, x, y) {
+                                Some(name) => format!("{} to the {}.", way, name),
+                                None => "The way is blocked.".to_string(),
+                            };
+                        }
+                        Some((x, y, _)) if game_map.entity_at(x, y) == Some(Entity::Enemy) => {
+                            let floor = floor_of(&game_map.name).unwrap_or(1);
+                            let enemies = floor_enemies(floor)
+                                .pick(&mut rand::thread_rng())
+                                .unwrap_or_else(|| vec![basic_enemies()[0].clone()]);
+                            let outcome = fight(
+                                &mut player,
+                                &mut game_map,
+                                &mut areas,
+                                enemies,
+                                &mut autosave,
+                            );
+                            new_action = match outcome {
+                                CombatOutcome::Victory => {
+                                    game_map.remove_entity(x, y);
+                                    "The guard is defeated.".to_string()
+                                }
+                                CombatOutcome::Defeat => "Player has respawned.".to_string(),
+                                CombatOutcome::Fled => "You back away from the guard.".to_string(),
+                                CombatOutcome::EnemiesFled => "The guard runs off.".to_string(),
+                            };
+                        }
+                        Some((x, y, Tile::Door)) if floor_of(&game_map.name).is_some() => {
+                            game_map.set_tile(x, y, Tile::OpenDoor);
+                            new_action = "You open the door.".to_string();
+                        }
+                        Some((_, _, Tile::Door)) => {
+                            new_action = "The door is locked.".to_string();
+                        }
+                        _ => {
+                            new_action = format!("Player moved {:?}", direction);
+                            if game_map.move_player(&direction) {
+                                game_map.advance_time(1);
+                                autosave.record_move();
+                                for (kind, change) in player.tick_status_effects() {
+                                    new_action.push(' ');
+                                    new_action.push_str(&describe_tick(kind, change, "you"));
+                                }
+                            }
+
+                            // Random enemy encounter logic, depending on the biome. Towns
+                            // and dungeons have their own dangers.
+                            let encounters = encounter_table(
+                                game_map.biome(game_map.player_x, game_map.player_y),
+                            );
+                            if game_map.is_overworld() && should_encounter_enemy(encounters.chance)
+                            {
+                                let enemies = random_encounter(&game_map);
+                                let outcome = fight(
+                                    &mut player,
+                                    &mut game_map,
+                                    &mut areas,
+                                    enemies,
+                                    &mut autosave,
+                                );
+                                if outcome == CombatOutcome::Defeat {
+                                    new_action = "Player has respawned.".to_string();
+                                }
+                            }
                         }
                     }
                 }
             }
+            "e" => {
+                let faced = game_map.faced_tile(player.facing);
+                let gathered = faced.and_then(|faced| {
+                    gather(&mut player, &mut game_map, faced, &mut rand::thread_rng())
+                });
+                new_action = match (gathered, faced) {
+                    (Some(message), _) => message,
+                    (None, Some((x, y, _))) if game_map.entity_at(x, y) == Some(Entity::Chest) => {
+                        open_chest(&mut player, &mut game_map, x, y)
+                    }
+                    (None, _) => campfire_menu(
+                        &mut player,
+                        &mut game_map,
+                        &areas,
+                        &save_folder,
+                        &character_name,
+                        &mut session_start,
+                        &mut autosave,
+                    )
+                    .unwrap_or_else(|| "There is nothing to interact with.".to_string()),
+                };
+            }
             "status" => {
                 println!("{}", player.display_status());
+                if game_map.is_overworld() {
+                    println!(
+                        "Biome: {}",
+                        game_map.biome(game_map.player_x, game_map.player_y).name()
+                    );
+                } else {
+                    println!("Area: {}", game_map.name);
+                }
+                println!("World seed: {}", game_map.seed);
                 println!("\nPress Enter to continue...");
                 let _ = io::stdin().read_line(&mut String::new());
                 new_action = "Viewed player status.".to_string();
@@ -632,6 +937,18 @@ fn game_loop(
                 new_action = "Viewed inventory.".to_string();
                 continue;
             }
+            "map" => {
+                world_map_screen(&game_map);
+                new_action = "Looked at the world map.".to_string();
+            }
+            "minimap" => {
+                show_minimap = !show_minimap;
+                new_action = if show_minimap {
+                    "Minimap shown.".to_string()
+                } else {
+                    "Minimap hidden.".to_string()
+                };
+            }
             "m" => {
                 // Handle menu
                 println!("Menu is under construction.");
@@ -649,25 +966,192 @@ fn game_loop(
 
         // Add the new action to the recent actions queue if not in combat
         if !player.in_combat {
-            if recent_actions.len() == max_recent_actions {
-                recent_actions.pop_front();
-            }
-            recent_actions.push_back(new_action.clone()); // Clone new_action here to keep the original value intact
+            // Clone new_action here to keep the original value intact
+            push_recent_action(&mut recent_actions, new_action.clone(), max_recent_actions);
         }
 
         // Check if player is dead and respawn if necessary
         if player.health <= 0 && !player.in_combat {
             // This block can be removed since respawn is handled after combat ends
             // Keeping it here as a fallback
-            player.respawn(&mut game_map);
+            player.respawn(&mut game_map, &mut areas);
             new_action = "Player has respawned.".to_string();
         }
     }
 }
 
-// Ensure skills are initialized when creating a new player
-fn create_new_player() -> Player {
-    let mut player = Player::new();
-    player.skills = initialize_skills();
-    player
+/// Ticks that pass while resting at a campfire.
+const REST_TICKS: u64 = 60;
+
+/// Shows the world map around the player until they go back, scrolling it
+/// with wasd.
+fn world_map_screen(game_map: &Map) {
+    let (columns, rows) = match term_size::dimensions() {
+        // Each square is two characters wide, and the menu takes four lines
+        Some((width, height)) => (width / 2, height.saturating_sub(4).max(1)),
+        None => (40, 20),
+    };
+    let (mut center_x, mut center_y) = (game_map.player_x, game_map.player_y);
+    // Scroll a quarter of the screen at a time
+    let step_x = (columns / 4).max(1) * WORLD_MAP_SCALE;
+    let step_y = (rows / 4).max(1) * WORLD_MAP_SCALE;
+
+    loop {
+        print!("\x1B[2J\x1B[1;1H");
+        println!("World map of the {} (w/a/s/d) scroll | (b)ack", game_map.name);
+        println!("P you | # campfire | + < > ways to other places\n");
+        print!(
+            "{}",
+            game_map.render_world_map(center_x, center_y, columns, rows)
+        );
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+        match input.trim() {
+            "w" => center_y = center_y.saturating_sub(step_y),
+            "s" => center_y = (center_y + step_y).min(game_map.height - 1),
+            "a" => center_x = center_x.saturating_sub(step_x),
+            "d" => center_x = (center_x + step_x).min(game_map.width - 1),
+            _ => return,
+        }
+    }
+}
+
+/// Save, rest, cook or set the respawn point at the campfire the player is
+/// facing. Returns the recent action to show, or None when there is no
+/// campfire to interact with.
+fn campfire_menu(
+    player: &mut Player,
+    game_map: &mut Map,
+    areas: &Areas,
+    save_folder: &Path,
+    character_name: &str,
+    session_start: &mut Instant,
+    autosave: &mut AutosaveTracker,
+) -> Option<String> {
+    let prompt = player.interact(game_map)?;
+    println!("\n{}", prompt);
+    print!("\nEnter your choice: ");
+    io::stdout().flush().unwrap();
+
+    let mut choice = String::new();
+    io::stdin()
+        .read_line(&mut choice)
+        .expect("Failed to read line");
+
+    let action = match choice.trim() {
+        "s" => {
+            record_play_time(player, session_start);
+            match save_world(player, game_map, areas, save_folder, character_name) {
+                Ok(()) => {
+                    // Autosave triggers count from this save on
+                    autosave.reset(player);
+                    "Saved the game at the campfire.".to_string()
+                }
+                Err(e) => format!("Failed to save game: {}", e),
+            }
+        }
+        "r" => {
+            player.health = player.max_health;
+            game_map.advance_time(REST_TICKS);
+            format!(
+                "Rested by the campfire. Health: {}/{}",
+                player.health, player.max_health
+            )
+        }
+        "c" => cook_raw_food(player),
+        "h" => {
+            if let Some((x, y, _)) = game_map.faced_tile(player.facing) {
+                player.respawn_point = Some(RespawnPoint {
+                    map: game_map.name.clone(),
+                    x,
+                    y,
+                });
+            }
+            "This campfire is now your respawn point.".to_string()
+        }
+        _ => "Left the campfire.".to_string(),
+    };
+    Some(action)
+}
+
+/// Fights `enemies` to the end. A win counts towards the starting quest and
+/// autosaving; a loss respawns the player at their campfire.
+fn fight(
+    player: &mut Player,
+    game_map: &mut Map,
+    areas: &mut Areas,
+    enemies: Vec<Enemy>,
+    autosave: &mut AutosaveTracker,
+) -> CombatOutcome {
+    let loot_tables = create_loot_tables();
+    player.enter_combat();
+    let (outcome, combat_result) = handle_combat(player, enemies, &loot_tables);
+    println!("{}", combat_result);
+    player.exit_combat();
+    if outcome == CombatOutcome::Victory {
+        record_victory(player, autosave);
+    }
+
+    // After combat ends, check if player is dead
+    if player.health <= 0 {
+        println!("You have been defeated!");
+        println!("Press Enter to respawn...");
+        let _ = io::stdin().read_line(&mut String::new());
+        player.respawn(game_map, areas);
+    }
+    outcome
+}
+
+/// A group of enemies from the encounter table of the biome the player is in.
+fn random_encounter(game_map: &Map) -> Vec<Enemy> {
+    encounter_table(game_map.biome(game_map.player_x, game_map.player_y))
+        .pick(&mut rand::thread_rng())
+        .unwrap_or_else(|| vec![basic_enemies()[0].clone()])
+}
+
+/// Width of `line` on screen, leaving out terminal escape codes.
+fn visible_width(line: &str) -> usize {
+    let mut in_escape = false;
+    line.chars()
+        .filter(|&c| {
+            if c == '\x1B' {
+                in_escape = true;
+            } else if in_escape {
+                in_escape = !c.is_ascii_alphabetic();
+                return false;
+            }
+            !in_escape
+        })
+        .count()
+}
+
+fn push_recent_action(recent_actions: &mut VecDeque<String>, action: String, max: usize) {
+    if recent_actions.len() >= max {
+        recent_actions.pop_front();
+    }
+    recent_actions.push_back(action);
+}
+
+// Count a won fight towards autosaving, and finish the starting quest on the first win
+fn record_victory(player: &mut Player, autosave: &mut AutosaveTracker) {
+    autosave.record_victory();
+    let starting_quest_id = starting_quest().id;
+    if player
+        .quests
+        .iter()
+        .any(|q| q.id == starting_quest_id && !q.is_completed())
+    {
+        player.complete_quest(starting_quest_id);
+    }
+}
+
+// Add the time since `session_start` to the player's play time
+fn record_play_time(player: &mut Player, session_start: &mut Instant) {
+    let played = session_start.elapsed().as_secs();
+    player.play_time += played;
+    *session_start += Duration::from_secs(played);
 }
@@ -1,49 +1,145 @@
+use std::collections::HashMap;
 use std::fmt;
+use crossterm::style::Stylize;
 use rand::Rng;
 use serde::{Serialize, Deserialize};
+use crate::dungeon::floor_name;
 use crate::player::Player;
-use term_size; // Ensure you have the term_size crate in your Cargo.toml
+use crate::terrain::{biome_at, generate_tile, water_at, Biome};
 
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
 pub enum Tile {
     Empty,
-    Player,
     Tree,
     Rock,
     Campfire,
+    Grass,
+    Sand,
+    Water,
+    Mountain,
+    FishingSpot,
+    Stump,
+    DepletedRock,
+    Wall,
+    Door,
+    OpenDoor,
+    StairsUp,
+    StairsDown,
 }
 
 impl Tile {
-    pub fn render(&self) -> &str {
-        match self {
-            Tile::Empty => ".",
-            Tile::Player => "P",
-            Tile::Tree => "t",
-            Tile::Rock => "r",
-            Tile::Campfire => "#",
-        }
-    }
-
-    pub fn to_char(&self) -> char {
+    pub fn to_char(self) -> char {
         match self {
             Tile::Empty => '.',
-            Tile::Player => 'P',
             Tile::Tree => 't',
             Tile::Rock => 'r',
             Tile::Campfire => '#',
+            Tile::Grass => '"',
+            Tile::Sand => ':',
+            Tile::Water => '~',
+            Tile::Mountain => '^',
+            Tile::FishingSpot => 'f',
+            Tile::Stump => 'n',
+            Tile::DepletedRock => 'o',
+            Tile::Wall => '=',
+            Tile::Door => '+',
+            Tile::OpenDoor => '/',
+            Tile::StairsUp => '<',
+            Tile::StairsDown => '>',
         }
     }
 
     pub fn from_char(c: char) -> Self {
+        Tile::parse_char(c).unwrap_or(Tile::Empty) // Default to Empty for unknown chars
+    }
+
+    // Tile chars must never be ASCII digits, which the map format uses for run lengths
+    pub fn parse_char(c: char) -> Option<Self> {
         match c {
-            '.' => Tile::Empty,
-            'P' => Tile::Player,
-            't' => Tile::Tree,
-            'r' => Tile::Rock,
-            '#' => Tile::Campfire,
-            _ => Tile::Empty, // Default to Empty for unknown chars
+            '.' => Some(Tile::Empty),
+            't' => Some(Tile::Tree),
+            'r' => Some(Tile::Rock),
+            '#' => Some(Tile::Campfire),
+            '"' => Some(Tile::Grass),
+            ':' => Some(Tile::Sand),
+            '~' => Some(Tile::Water),
+            '^' => Some(Tile::Mountain),
+            'f' => Some(Tile::FishingSpot),
+            'n' => Some(Tile::Stump),
+            'o' => Some(Tile::DepletedRock),
+            '=' => Some(Tile::Wall),
+            '+' => Some(Tile::Door),
+            '/' => Some(Tile::OpenDoor),
+            '<' => Some(Tile::StairsUp),
+            '>' => Some(Tile::StairsDown),
+            _ => None,
         }
     }
+
+    /// Whether the player can stand on this tile.
+    pub fn is_walkable(self) -> bool {
+        matches!(
+            self,
+            Tile::Empty | Tile::Campfire | Tile::Grass | Tile::Sand | Tile::OpenDoor
+        )
+    }
+
+    /// Whether the player can't see past this tile.
+    pub fn blocks_sight(self) -> bool {
+        matches!(
+            self,
+            Tile::Tree | Tile::Rock | Tile::Mountain | Tile::Wall | Tile::Door
+        )
+    }
+}
+
+/// How the player is drawn over the tile they stand on.
+pub const PLAYER_SYMBOL: char = 'P';
+
+/// Something standing on a tile without being part of the terrain. The map
+/// keeps these apart from its tiles and draws them on top.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Entity {
+    Enemy,
+    Chest,
+}
+
+impl Entity {
+    pub fn to_char(self) -> char {
+        match self {
+            Entity::Enemy => 'E',
+            Entity::Chest => '$',
+        }
+    }
+
+    pub fn parse_char(c: char) -> Option<Self> {
+        match c {
+            'E' => Some(Entity::Enemy),
+            '$' => Some(Entity::Chest),
+            _ => None,
+        }
+    }
+}
+
+/// An entity and the tile it stands on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlacedEntity {
+    pub x: usize,
+    pub y: usize,
+    pub entity: Entity,
+}
+
+/// Reads one char of a map file or layout into the ground and whatever
+/// stands on it. Maps written before entities left the grid have the player,
+/// guards and chests in place of the ground, which is taken to be empty.
+fn parse_cell(c: char) -> Option<(Tile, Option<Entity>)> {
+    if c == PLAYER_SYMBOL {
+        return Some((Tile::Empty, None));
+    }
+    match Entity::parse_char(c) {
+        Some(entity) => Some((Tile::Empty, Some(entity))),
+        None => Tile::parse_char(c).map(|tile| (tile, None)),
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
@@ -54,61 +150,482 @@ pub enum Direction {
     Right,
 }
 
+/// Version of the run-length encoded format written by `Map::serialize_map`.
+///
+/// Version 1 stored every row of a fixed size map, version 2 only stores the
+/// chunks that differ from what the world seed generates.
+pub const MAP_FORMAT_VERSION: u32 = 2;
+
+/// Side length of a chunk, in tiles.
+pub const CHUNK_SIZE: usize = 30;
+
+/// Width and height of a new world, in tiles. Nobody is expected to walk to
+/// the edge.
+pub const WORLD_SIZE: usize = CHUNK_SIZE * 65_536;
+
+/// Chunks kept loaded in each direction around the chunk the player is in.
+pub const LOADED_CHUNK_RADIUS: usize = 2;
+
+/// How far the player can see in every direction, in tiles.
+pub const SIGHT_RADIUS: usize = 8;
+
+/// Side length, in tiles, of the square one character of the world map
+/// stands for.
+pub const WORLD_MAP_SCALE: usize = 4;
+
+/// One bit per tile the player has seen, for each chunk they have looked into.
+pub type Explored = HashMap<(usize, usize), Vec<u64>>;
+
+/// Words of the bit set recording which tiles of a chunk have been seen.
+const EXPLORED_WORDS: usize = (CHUNK_SIZE * CHUNK_SIZE).div_ceil(64);
+
+/// Name of the map a new world starts in.
+pub const OVERWORLD: &str = "overworld";
+
+/// Name of the town whose gate is next to the starting campfire.
+pub const TOWN: &str = "town";
+
+/// First word of the header line of an encoded map.
+const MAP_MAGIC: &str = "RPGMAP";
+
+/// Why an encoded map could not be read back.
+#[derive(Debug, PartialEq)]
+pub enum MapFormatError {
+    BadHeader,
+    UnsupportedVersion(u32),
+    BadRow(usize),
+    BadChunk(usize),
+    SizeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+}
+
+impl fmt::Display for MapFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapFormatError::BadHeader => write!(f, "missing or malformed map header"),
+            MapFormatError::UnsupportedVersion(version) => {
+                write!(f, "unsupported map format version {}", version)
+            }
+            MapFormatError::BadRow(row) => write!(f, "malformed map row {}", row),
+            MapFormatError::BadChunk(line) => write!(f, "malformed chunk at line {}", line),
+            MapFormatError::SizeMismatch { expected, found } => write!(
+                f,
+                "map is {}x{} but its header says {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+        }
+    }
+}
+
+/// A `CHUNK_SIZE` x `CHUNK_SIZE` block of tiles, stored row by row.
+#[derive(Debug, Clone, PartialEq)]
+struct Chunk {
+    tiles: Vec<Tile>,
+    // Set once the chunk differs from what `generate_chunk` gives, so it has to be saved
+    modified: bool,
+}
+
+impl Chunk {
+    fn get(&self, x: usize, y: usize) -> Tile {
+        self.tiles[(y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE]
+    }
+
+    fn set(&mut self, x: usize, y: usize, tile: Tile) {
+        self.tiles[(y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE] = tile;
+    }
+}
+
+/// Chunk coordinates of the chunk holding tile `(x, y)`.
+fn chunk_of(x: usize, y: usize) -> (usize, usize) {
+    (x / CHUNK_SIZE, y / CHUNK_SIZE)
+}
+
+/// Turns what the player typed as a world seed into one. Numbers are used
+/// as they are, any other text is hashed, and an empty answer picks a random
+/// seed.
+pub fn parse_seed(input: &str) -> u64 {
+    let input = input.trim();
+    if input.is_empty() {
+        return rand::thread_rng().gen();
+    }
+    input.parse().unwrap_or_else(|_| {
+        // 64-bit FNV-1a, so a word gives the same world everywhere
+        input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    })
+}
+
+/// Closest spot to `(center_x, center_y)`, searching outwards ring by ring,
+/// where the player and the campfire below them start on open grassland.
+/// Falls back to the center if there is none nearby.
+fn find_spawn(
+    seed: u64,
+    width: usize,
+    height: usize,
+    center_x: usize,
+    center_y: usize,
+) -> (usize, usize) {
+    let suitable = |x: usize, y: usize| {
+        y + 1 < height
+            && biome_at(seed, x, y) == Biome::Grassland
+            && biome_at(seed, x, y + 1) == Biome::Grassland
+            && !water_at(seed, x, y)
+            && !water_at(seed, x, y + 1)
+    };
+    for radius in 0..CHUNK_SIZE * 8 {
+        let (left, top) = (center_x.saturating_sub(radius), center_y.saturating_sub(radius));
+        let right = (center_x + radius).min(width - 1);
+        let bottom = (center_y + radius).min(height - 1);
+        for y in top..=bottom {
+            for x in left..=right {
+                let on_ring = x == left || x == right || y == top || y == bottom;
+                if on_ring && suitable(x, y) {
+                    return (x, y);
+                }
+            }
+        }
+    }
+    (center_x, center_y)
+}
+
+fn generate_chunk(seed: u64, chunk_x: usize, chunk_y: usize) -> Chunk {
+    let (left, top) = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+    let tiles = (0..CHUNK_SIZE * CHUNK_SIZE)
+        .map(|i| generate_tile(seed, left + i % CHUNK_SIZE, top + i / CHUNK_SIZE))
+        .collect();
+    Chunk {
+        tiles,
+        modified: false,
+    }
+}
+
+/// A gathered tile waiting to grow back into `tile`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Regrowth {
+    pub x: usize,
+    pub y: usize,
+    pub tile: Tile,
+    pub ready_at: u64,
+}
+
+/// Leads the player from the door at `(x, y)` to the map named `target`.
+/// Without an `arrival` position the player returns to where they last stood
+/// on that map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Portal {
+    pub x: usize,
+    pub y: usize,
+    pub target: String,
+    pub arrival: Option<(usize, usize)>,
+}
+
+// The tiles are stored in map.txt, never alongside the rest of the map in character.json
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Map {
+    pub name: String,
     pub width: usize,
     pub height: usize,
-    pub tiles: Vec<Vec<Tile>>,
+    pub seed: u64,
+    #[serde(skip)]
+    chunks: HashMap<(usize, usize), Chunk>,
     pub player_x: usize,
     pub player_y: usize,
     pub view_radius: usize,
     pub campfire_x: usize,
     pub campfire_y: usize,
+    pub world_time: u64, // Ticks passed in the world, one per move
+    pub regrowing: Vec<Regrowth>,
+    pub portals: Vec<Portal>,
+    #[serde(with = "explored_chunks")]
+    pub explored: Explored,
+    pub entities: Vec<PlacedEntity>,
 }
 
 impl Map {
-    pub fn new(width: usize, height: usize) -> Self {
-        let mut tiles = vec![vec![Tile::Empty; width]; height];
-        // Randomly generate map with obstacles (tree and rock)
-        let mut rng = rand::thread_rng();
-        for y in 0..height {
-            for x in 0..width {
-                let roll: f32 = rng.gen();
-                if roll < 0.05 {
-                    tiles[y][x] = Tile::Rock;
-                } else if roll < 0.15 {
-                    tiles[y][x] = Tile::Tree;
+    /// Generates a world from `seed`. The same seed always gives the same
+    /// world.
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        // Start near the center of the middle chunk, on land with room for a campfire
+        let center_x = ((width / CHUNK_SIZE / 2) * CHUNK_SIZE + CHUNK_SIZE / 2).min(width - 1);
+        let center_y = ((height / CHUNK_SIZE / 2) * CHUNK_SIZE + CHUNK_SIZE / 2).min(height - 2);
+        let (start_x, start_y) = find_spawn(seed, width, height, center_x, center_y);
+        let mut map = Self::empty(width, height, seed, start_x, start_y);
+        map.set_tile(start_x, start_y, Tile::Empty);
+        // Place campfire south of player
+        map.campfire_x = start_x;
+        map.campfire_y = start_y + 1;
+        map.set_tile(map.campfire_x, map.campfire_y, Tile::Campfire);
+        // The town gate stands two steps east, with a clear tile in front of it
+        if start_x + 2 < width {
+            map.set_tile(start_x + 1, start_y, Tile::Empty);
+            map.set_tile(start_x + 2, start_y, Tile::Door);
+            map.portals.push(Portal {
+                x: start_x + 2,
+                y: start_y,
+                target: TOWN.to_string(),
+                arrival: None,
+            });
+        }
+        // And stairs down into the dungeon two steps west
+        if start_x >= 2 {
+            map.set_tile(start_x - 1, start_y, Tile::Empty);
+            map.set_tile(start_x - 2, start_y, Tile::StairsDown);
+            map.portals.push(Portal {
+                x: start_x - 2,
+                y: start_y,
+                target: floor_name(1),
+                arrival: None,
+            });
+        }
+        map.place_player(start_x, start_y);
+        map
+    }
+
+    /// A fixed size map named `name`, drawn row by row with tile and entity
+    /// chars. The player starts on the `P` and the first `#`, if any, is the
+    /// campfire.
+    pub fn from_layout(name: &str, rows: &[&str], portals: Vec<Portal>) -> Self {
+        let cells: Vec<Vec<(Tile, Option<Entity>)>> = rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| parse_cell(c).unwrap_or((Tile::Empty, None)))
+                    .collect()
+            })
+            .collect();
+        let tiles: Vec<Vec<Tile>> = cells
+            .iter()
+            .map(|row| row.iter().map(|&(tile, _)| tile).collect())
+            .collect();
+        let width = tiles.iter().map(Vec::len).max().unwrap_or(0);
+        let mut map = Self::from_rows(width, tiles.len(), &tiles);
+        map.name = name.to_string();
+        map.portals = portals;
+        for (y, row) in cells.iter().enumerate() {
+            for (x, &(_, entity)) in row.iter().enumerate() {
+                if let Some(entity) = entity {
+                    map.place_entity(x, y, entity);
                 }
             }
         }
-        // Place the player in the center of the initial chunk
-        let chunk_size = 30;
-        let chunk_center_x = chunk_size / 2;
-        let chunk_center_y = chunk_size / 2;
-        tiles[chunk_center_y][chunk_center_x] = Tile::Player;
-        // Set the player's position
-        let player_x = chunk_center_x;
-        let player_y = chunk_center_y;
-        tiles[player_y][player_x] = Tile::Player;
-        // Place campfire south of player
-        let campfire_x = chunk_center_x;
-        let campfire_y = chunk_center_y + 1;
-        if campfire_y < height {
-            tiles[campfire_y][campfire_x] = Tile::Campfire;
+        let find = |wanted: char| {
+            rows.iter()
+                .enumerate()
+                .find_map(|(y, row)| row.chars().position(|c| c == wanted).map(|x| (x, y)))
+        };
+        if let Some((x, y)) = find(PLAYER_SYMBOL) {
+            map.player_x = x;
+            map.player_y = y;
         }
-        Self {
+        // Without a campfire the player respawns where they start
+        let (campfire_x, campfire_y) =
+            find(Tile::Campfire.to_char()).unwrap_or((map.player_x, map.player_y + 1));
+        map.campfire_x = campfire_x;
+        map.campfire_y = campfire_y;
+        map
+    }
+
+    /// A map with nothing loaded yet and the player at `(player_x, player_y)`.
+    fn empty(width: usize, height: usize, seed: u64, player_x: usize, player_y: usize) -> Self {
+        Map {
+            name: OVERWORLD.to_string(),
             width,
             height,
-            tiles,
+            seed,
+            chunks: HashMap::new(),
             player_x,
             player_y,
             view_radius: 15, // Default to 15 for a 30x30 view
-            campfire_x,
-            campfire_y,
+            campfire_x: player_x,
+            campfire_y: player_y,
+            world_time: 0,
+            regrowing: Vec::new(),
+            portals: Vec::new(),
+            explored: HashMap::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    /// A map whose every tile is given row by row, as in the fixed size map
+    /// files. All chunks are kept as modified since no seed produced them.
+    fn from_rows(width: usize, height: usize, rows: &[Vec<Tile>]) -> Self {
+        let mut map = Self::empty(width, height, 0, 0, 0);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                map.set_tile(x, y, tile);
+            }
+        }
+        // Chunks that hang over the edge are filled with generated tiles nobody can reach
+        for chunk in map.chunks.values_mut() {
+            chunk.modified = true;
+        }
+        map
+    }
+
+    /// The tile at `(x, y)`, which must be on the map.
+    pub fn tile(&self, x: usize, y: usize) -> Tile {
+        match self.chunks.get(&chunk_of(x, y)) {
+            Some(chunk) => chunk.get(x, y),
+            None => generate_tile(self.seed, x, y),
+        }
+    }
+
+    fn load_chunk(&mut self, chunk_x: usize, chunk_y: usize) -> &mut Chunk {
+        let seed = self.seed;
+        self.chunks
+            .entry((chunk_x, chunk_y))
+            .or_insert_with(|| generate_chunk(seed, chunk_x, chunk_y))
+    }
+
+    /// Loads the chunks around the player and drops unmodified chunks that
+    /// are far away; they are generated again when the player comes back.
+    pub fn load_chunks_around_player(&mut self) {
+        let (center_x, center_y) = chunk_of(self.player_x, self.player_y);
+        let last_x = ((self.width - 1) / CHUNK_SIZE).min(center_x + LOADED_CHUNK_RADIUS);
+        let last_y = ((self.height - 1) / CHUNK_SIZE).min(center_y + LOADED_CHUNK_RADIUS);
+        for chunk_y in center_y.saturating_sub(LOADED_CHUNK_RADIUS)..=last_y {
+            for chunk_x in center_x.saturating_sub(LOADED_CHUNK_RADIUS)..=last_x {
+                self.load_chunk(chunk_x, chunk_y);
+            }
+        }
+        // Keep one extra ring so walking back and forth over a border doesn't regenerate
+        self.chunks.retain(|&(chunk_x, chunk_y), chunk| {
+            chunk.modified
+                || (chunk_x.abs_diff(center_x) <= LOADED_CHUNK_RADIUS + 1
+                    && chunk_y.abs_diff(center_y) <= LOADED_CHUNK_RADIUS + 1)
+        });
+    }
+
+    /// The biome the world generator put at `(x, y)`.
+    pub fn biome(&self, x: usize, y: usize) -> Biome {
+        biome_at(self.seed, x, y)
+    }
+
+    /// Moves the player to `(x, y)`. The player isn't part of the terrain,
+    /// so the tile they stand on is left as it is.
+    pub fn place_player(&mut self, x: usize, y: usize) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        self.player_x = x;
+        self.player_y = y;
+        self.load_chunks_around_player();
+        self.reveal_around_player();
+    }
+
+    /// The entity standing on `(x, y)`, if any.
+    pub fn entity_at(&self, x: usize, y: usize) -> Option<Entity> {
+        self.entities
+            .iter()
+            .find(|placed| (placed.x, placed.y) == (x, y))
+            .map(|placed| placed.entity)
+    }
+
+    /// Puts `entity` on `(x, y)`, replacing whatever stood there.
+    pub fn place_entity(&mut self, x: usize, y: usize, entity: Entity) {
+        self.remove_entity(x, y);
+        self.entities.push(PlacedEntity { x, y, entity });
+    }
+
+    /// Takes the entity off `(x, y)`, returning it.
+    pub fn remove_entity(&mut self, x: usize, y: usize) -> Option<Entity> {
+        let index = self
+            .entities
+            .iter()
+            .position(|placed| (placed.x, placed.y) == (x, y))?;
+        Some(self.entities.remove(index).entity)
+    }
+
+    /// Whether the player has ever seen the tile at `(x, y)`.
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        let index = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+        self.explored
+            .get(&chunk_of(x, y))
+            .is_some_and(|bits| bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    fn mark_explored(&mut self, x: usize, y: usize) {
+        let index = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+        let bits = self
+            .explored
+            .entry(chunk_of(x, y))
+            .or_insert_with(|| vec![0; EXPLORED_WORDS]);
+        bits[index / 64] |= 1 << (index % 64);
+    }
+
+    /// Whether the player can see the tile at `(x, y)`: it is within
+    /// `SIGHT_RADIUS` and nothing on the straight line to it blocks the view.
+    /// Tiles that block sight are also seen when the ground next to them is,
+    /// so walls and tree lines show their whole length.
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        if self.in_clear_view(x, y) {
+            return true;
+        }
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        self.tile(x, y).blocks_sight()
+            && neighbours.iter().any(|&(nx, ny)| {
+                nx < self.width
+                    && ny < self.height
+                    && !self.tile(nx, ny).blocks_sight()
+                    && self.in_clear_view(nx, ny)
+            })
+    }
+
+    fn in_clear_view(&self, x: usize, y: usize) -> bool {
+        let (dx, dy) = (x.abs_diff(self.player_x), y.abs_diff(self.player_y));
+        if dx * dx + dy * dy > SIGHT_RADIUS * SIGHT_RADIUS {
+            return false;
         }
+        !line_between((self.player_x, self.player_y), (x, y))
+            .any(|(line_x, line_y)| self.tile(line_x, line_y).blocks_sight())
     }
 
-    pub fn move_player(&mut self, direction: &Direction) {
+    /// Marks every tile the player can see as explored.
+    pub fn reveal_around_player(&mut self) {
+        let left = self.player_x.saturating_sub(SIGHT_RADIUS);
+        let right = (self.player_x + SIGHT_RADIUS).min(self.width - 1);
+        let top = self.player_y.saturating_sub(SIGHT_RADIUS);
+        let bottom = (self.player_y + SIGHT_RADIUS).min(self.height - 1);
+        for y in top..=bottom {
+            for x in left..=right {
+                if self.is_visible(x, y) {
+                    self.mark_explored(x, y);
+                }
+            }
+        }
+    }
+
+    /// How the tile at `(x, y)` is drawn, with the player or any entity on
+    /// top: as it is while the player can see it, dimmed once it is only
+    /// remembered, and blank before it is explored. Entities out of sight
+    /// aren't drawn, since the player can't know they are still there.
+    fn render_tile(&self, x: usize, y: usize) -> String {
+        if (x, y) == (self.player_x, self.player_y) {
+            return PLAYER_SYMBOL.to_string();
+        }
+        if self.is_visible(x, y) {
+            match self.entity_at(x, y) {
+                Some(entity) => entity.to_char().to_string(),
+                None => self.tile(x, y).to_string(),
+            }
+        } else if self.is_explored(x, y) {
+            self.tile(x, y).to_string().dim().to_string()
+        } else {
+            " ".to_string()
+        }
+    }
+
+    /// Steps the player one tile in `direction`, returning whether they moved.
+    /// Walls, water, entities and the map edge stop them.
+    pub fn move_player(&mut self, direction: &Direction) -> bool {
         let (new_x, new_y) = match direction {
             Direction::Up => (self.player_x, self.player_y.saturating_sub(1)),
             Direction::Down => (
@@ -122,12 +639,13 @@ impl Map {
             ),
         };
 
-        if self.tiles[new_y][new_x] == Tile::Empty || self.tiles[new_y][new_x] == Tile::Campfire {
-            self.tiles[self.player_y][self.player_x] = Tile::Empty;
-            self.player_x = new_x;
-            self.player_y = new_y;
-            self.tiles[self.player_y][self.player_x] = Tile::Player;
+        let moved = (new_x, new_y) != (self.player_x, self.player_y)
+            && self.tile(new_x, new_y).is_walkable()
+            && self.entity_at(new_x, new_y).is_none();
+        if moved {
+            self.place_player(new_x, new_y);
         }
+        moved
     }
 
     pub fn render(&self) -> String {
@@ -135,24 +653,16 @@ impl Map {
 
         // Define the boundaries of the viewport
         let half_radius = self.view_radius;
-        let start_x = if self.player_x > half_radius {
-            self.player_x - half_radius
-        } else {
-            0
-        };
+        let start_x = self.player_x.saturating_sub(half_radius);
         let end_x = usize::min(self.player_x + half_radius, self.width - 1);
-        let start_y = if self.player_y > half_radius {
-            self.player_y - half_radius
-        } else {
-            0
-        };
+        let start_y = self.player_y.saturating_sub(half_radius);
         let end_y = usize::min(self.player_y + half_radius, self.height - 1);
 
         // Iterate through the viewport area
         for y in start_y..=end_y {
             for x in start_x..=end_x {
                 // Append the tile representation followed by a space for even spacing
-                output.push_str(self.tiles[y][x].render());
+                output.push_str(&self.render_tile(x, y));
                 output.push(' ');
             }
             output.push('\n');
@@ -171,19 +681,11 @@ impl Map {
             let half_viewport_width = viewport_width / 2;
             let half_viewport_height = viewport_height / 2;
 
-            let start_x = if self.player_x >= half_viewport_width {
-                self.player_x - half_viewport_width
-            } else {
-                0
-            };
+            let start_x = self.player_x.saturating_sub(half_viewport_width);
 
             let end_x = usize::min(start_x + viewport_width - 1, self.width - 1);
 
-            let start_y = if self.player_y >= half_viewport_height {
-                self.player_y - half_viewport_height
-            } else {
-                0
-            };
+            let start_y = self.player_y.saturating_sub(half_viewport_height);
 
             let end_y = usize::min(start_y + viewport_height - 1, self.height - 1);
 
@@ -191,7 +693,7 @@ impl Map {
 
             for y in start_y..=end_y {
                 for x in start_x..=end_x {
-                    output.push_str(self.tiles[y][x].render());
+                    output.push_str(&self.render_tile(x, y));
                     output.push(' ');
                 }
                 output.push('\n');
@@ -203,25 +705,210 @@ impl Map {
         }
     }
 
+    /// Draws the world map: `columns` x `rows` characters around the one
+    /// holding `(center_x, center_y)`, each standing for a square of
+    /// `WORLD_MAP_SCALE` tiles. Squares are aligned to the map, so the picture
+    /// stays put while it is scrolled.
+    pub fn render_world_map(
+        &self,
+        center_x: usize,
+        center_y: usize,
+        columns: usize,
+        rows: usize,
+    ) -> String {
+        let left = (center_x / WORLD_MAP_SCALE).saturating_sub(columns / 2);
+        let top = (center_y / WORLD_MAP_SCALE).saturating_sub(rows / 2);
+        let right = (left + columns).min(self.width.div_ceil(WORLD_MAP_SCALE));
+        let bottom = (top + rows).min(self.height.div_ceil(WORLD_MAP_SCALE));
+
+        let mut output = String::new();
+        for block_y in top..bottom {
+            for block_x in left..right {
+                output.push(self.world_map_symbol(block_x, block_y));
+                output.push(' ');
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// The character for one square of the world map. The player, then
+    /// campfires, then doors and stairs that lead somewhere are marked;
+    /// otherwise the explored tile the square holds most of is shown, or
+    /// nothing if the player hasn't seen any of it.
+    fn world_map_symbol(&self, block_x: usize, block_y: usize) -> char {
+        let (left, top) = (block_x * WORLD_MAP_SCALE, block_y * WORLD_MAP_SCALE);
+        let right = (left + WORLD_MAP_SCALE).min(self.width);
+        let bottom = (top + WORLD_MAP_SCALE).min(self.height);
+        if (left..right).contains(&self.player_x) && (top..bottom).contains(&self.player_y) {
+            return PLAYER_SYMBOL;
+        }
+
+        let mut counts: Vec<(Tile, usize)> = Vec::new();
+        let mut landmark = None;
+        for y in top..bottom {
+            for x in left..right {
+                if !self.is_explored(x, y) {
+                    continue;
+                }
+                let tile = self.tile(x, y);
+                if tile == Tile::Campfire {
+                    return tile.to_char();
+                }
+                if self.portal_at(x, y).is_some() {
+                    landmark = Some(tile);
+                }
+                match counts.iter_mut().find(|(counted, _)| *counted == tile) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((tile, 1)),
+                }
+            }
+        }
+        // On a tie the tile found first wins
+        let dominant = counts
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|&(tile, _)| tile);
+        landmark.or(dominant).map_or(' ', Tile::to_char)
+    }
+
+    /// Encodes the map as a header line followed by every modified chunk,
+    /// each as a `CHUNK` line and one run-length encoded line per row:
+    ///
+    /// ```text
+    /// RPGMAP <version> <width> <height> <campfire_x> <campfire_y> <seed>
+    /// CHUNK <chunk_x> <chunk_y>
+    /// 12.t4.r...
+    /// ```
+    ///
+    /// Each run is an optional count followed by the tile char; a missing
+    /// count means a single tile. Chunks are written in order so equal maps
+    /// encode to equal text.
     pub fn serialize_map(&self) -> String {
-        let mut serialized = String::new();
-        for (y, row) in self.tiles.iter().enumerate() {
-            for (x, tile) in row.iter().enumerate() {
-                let tile_char = tile.to_char();
-                serialized.push(tile_char);
-                log::debug!("Serializing Tile at ({}, {}): {}", x, y, tile_char);
+        let mut serialized = format!(
+            "{} {} {} {} {} {} {}\n",
+            MAP_MAGIC,
+            MAP_FORMAT_VERSION,
+            self.width,
+            self.height,
+            self.campfire_x,
+            self.campfire_y,
+            self.seed
+        );
+        let mut modified: Vec<(&(usize, usize), &Chunk)> =
+            self.chunks.iter().filter(|(_, chunk)| chunk.modified).collect();
+        modified.sort_by_key(|(&(chunk_x, chunk_y), _)| (chunk_y, chunk_x));
+        for (&(chunk_x, chunk_y), chunk) in modified {
+            serialized.push_str(&format!("CHUNK {} {}\n", chunk_x, chunk_y));
+            for row in chunk.tiles.chunks(CHUNK_SIZE) {
+                encode_row(&mut serialized, row);
             }
-            serialized.push('\n');
         }
         serialized
     }
 
+    /// Whether `data` is in the format written by `serialize_map`, as opposed
+    /// to the legacy one char per tile layout.
+    pub fn is_encoded_map(data: &str) -> bool {
+        data.starts_with(MAP_MAGIC)
+    }
+
+    /// Reads a map written by `serialize_map`, or by the fixed size version
+    /// 1 format, placing the player at the given position.
     pub fn deserialize_map(
+        data: &str,
+        player_x: usize,
+        player_y: usize,
+    ) -> Result<Self, MapFormatError> {
+        let mut lines = data.lines();
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or(MapFormatError::BadHeader)?
+            .split_whitespace()
+            .collect();
+        if header.len() < 2 || header[0] != MAP_MAGIC {
+            return Err(MapFormatError::BadHeader);
+        }
+        let numbers: Vec<u64> = header[1..]
+            .iter()
+            .map(|value| value.parse().map_err(|_| MapFormatError::BadHeader))
+            .collect::<Result<_, _>>()?;
+        let version = numbers[0] as u32;
+        let expected_fields = match version {
+            1 => 5,
+            MAP_FORMAT_VERSION => 6,
+            _ => return Err(MapFormatError::UnsupportedVersion(version)),
+        };
+        if numbers.len() != expected_fields || numbers[1] == 0 || numbers[2] == 0 {
+            return Err(MapFormatError::BadHeader);
+        }
+        let (width, height) = (numbers[1] as usize, numbers[2] as usize);
+
+        let mut map = if version == 1 {
+            Self::from_rows(width, height, &decode_rows(lines, width, height)?)
+        } else {
+            let mut map = Self::empty(width, height, numbers[5], player_x, player_y);
+            map.read_chunks(lines)?;
+            map
+        };
+        map.campfire_x = numbers[3] as usize;
+        map.campfire_y = numbers[4] as usize;
+
+        // Set the player's position
+        map.place_player(player_x, player_y);
+        Ok(map)
+    }
+
+    /// Reads the `CHUNK` blocks of a version 2 map, marking each as modified.
+    fn read_chunks<'a>(
+        &mut self,
+        lines: impl Iterator<Item = &'a str>,
+    ) -> Result<(), MapFormatError> {
+        let mut lines = lines.enumerate().map(|(number, line)| (number + 2, line));
+        while let Some((number, line)) = lines.next() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let position = match fields.as_slice() {
+                ["CHUNK", chunk_x, chunk_y] => chunk_x.parse().ok().zip(chunk_y.parse().ok()),
+                _ => None,
+            };
+            let (chunk_x, chunk_y): (usize, usize) =
+                position.ok_or(MapFormatError::BadChunk(number))?;
+            // A huge index from an edited save must not overflow
+            let (left, top) = chunk_x
+                .checked_mul(CHUNK_SIZE)
+                .zip(chunk_y.checked_mul(CHUNK_SIZE))
+                .ok_or(MapFormatError::BadChunk(number))?;
+            if left >= self.width || top >= self.height {
+                return Err(MapFormatError::BadChunk(number));
+            }
+
+            let mut tiles = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE);
+            for row_y in 0..CHUNK_SIZE {
+                let (number, row) = lines.next().ok_or(MapFormatError::BadChunk(number))?;
+                let row = decode_row(row, CHUNK_SIZE).ok_or(MapFormatError::BadRow(number))?;
+                for (row_x, &(tile, entity)) in row.iter().enumerate() {
+                    tiles.push(tile);
+                    if let Some(entity) = entity {
+                        self.place_entity(left + row_x, top + row_y, entity);
+                    }
+                }
+            }
+            self.chunks.insert((chunk_x, chunk_y), Chunk { tiles, modified: true });
+        }
+        Ok(())
+    }
+
+    /// Reads the one char per tile `map.txt` written before the map format
+    /// was versioned.
+    pub fn deserialize_legacy_map(
         width: usize,
         height: usize,
         data: &str,
         player_x: usize,
         player_y: usize,
+        campfire_x: usize,
+        campfire_y: usize,
     ) -> Self {
         let mut tiles = vec![vec![Tile::Empty; width]; height];
         
@@ -232,86 +919,579 @@ impl Map {
             }
         }
 
-        // Set the player's position
-        tiles[player_y][player_x] = Tile::Player;
+        let mut map = Self::from_rows(width, height, &tiles);
+        map.campfire_x = campfire_x;
+        map.campfire_y = campfire_y;
 
-        // Initialize campfire positions (ensure these are set appropriately)
-        // For example, you might have stored campfire_x and campfire_y in CharacterSave
-        // If not, you need to handle their deserialization accordingly
-        let campfire_x = 0; // Placeholder: Set actual value
-        let campfire_y = 0; // Placeholder: Set actual value
+        // Set the player's position
+        map.place_player(player_x, player_y);
+        map
+    }
 
-        Map {
-            width,
-            height,
-            tiles,
-            player_x,
-            player_y,
-            view_radius: 15, // Adjust as needed
-            campfire_x,
-            campfire_y,
+    pub fn interact(&self, player: &Player) -> Option<String> {
+        if self.is_adjacent_and_facing_campfire(player) {
+            Some("The campfire crackles. (s)ave, (r)est, (c)ook, set (h)ome, (b)ack".to_string())
+        } else {
+            None
         }
     }
 
-    pub fn interact(&self, player: &Player) -> Option<String> {
-        let (x, y) = match player.facing {
-            Direction::Up => (self.player_x, self.player_y.saturating_sub(1)),
-            Direction::Down => (self.player_x, (self.player_y + 1).min(self.height - 1)),
-            Direction::Left => (self.player_x.saturating_sub(1), self.player_y),
-            Direction::Right => ((self.player_x + 1).min(self.width - 1), self.player_y),
+    /// The tile next to the player in `facing`, with its position, if it is on the map.
+    pub fn faced_tile(&self, facing: Direction) -> Option<(usize, usize, Tile)> {
+        let (x, y) = match facing {
+            Direction::Up => (self.player_x, self.player_y.checked_sub(1)?),
+            Direction::Down => (self.player_x, self.player_y + 1),
+            Direction::Left => (self.player_x.checked_sub(1)?, self.player_y),
+            Direction::Right => (self.player_x + 1, self.player_y),
         };
-        
-        if self.tiles[y][x] == Tile::Campfire {
-            Some("Would you like to save the game? (y/n)".to_string())
+
+        if x < self.width && y < self.height {
+            Some((x, y, self.tile(x, y)))
         } else {
             None
         }
     }
 
+    /// Whether this is the generated world rather than a town or building.
+    pub fn is_overworld(&self) -> bool {
+        self.name == OVERWORLD
+    }
+
+    /// The portal on the door at `(x, y)`, if that door leads anywhere.
+    pub fn portal_at(&self, x: usize, y: usize) -> Option<&Portal> {
+        self.portals
+            .iter()
+            .find(|portal| (portal.x, portal.y) == (x, y))
+    }
+
+    /// Moves the world clock forward and grows back any gathered tiles
+    /// whose time has come.
+    pub fn advance_time(&mut self, ticks: u64) {
+        self.world_time += ticks;
+        let world_time = self.world_time;
+        let (ready, waiting) = self
+            .regrowing
+            .iter()
+            .partition(|regrowth| regrowth.ready_at <= world_time);
+        self.regrowing = waiting;
+        for regrowth in ready {
+            self.set_tile(regrowth.x, regrowth.y, regrowth.tile);
+        }
+    }
+
+    /// Replaces the tile at `(x, y)` with `depleted` until `ticks` have
+    /// passed.
+    pub fn deplete_tile(&mut self, x: usize, y: usize, depleted: Tile, ticks: u64) {
+        self.regrowing.push(Regrowth {
+            x,
+            y,
+            tile: self.tile(x, y),
+            ready_at: self.world_time + ticks,
+        });
+        self.set_tile(x, y, depleted);
+    }
+
     pub fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
         if y < self.height && x < self.width {
-            self.tiles[y][x] = tile;
+            let (chunk_x, chunk_y) = chunk_of(x, y);
+            let chunk = self.load_chunk(chunk_x, chunk_y);
+            chunk.set(x, y, tile);
+            chunk.modified = true;
         }
     }
 
     pub fn is_adjacent_and_facing_campfire(&self, player: &Player) -> bool {
-        let (player_x, player_y) = (self.player_x, self.player_y);
-        let (adjacent_x, adjacent_y) = match player.facing {
-            Direction::Up => (self.player_x, self.player_y.saturating_sub(1)),
-            Direction::Down => (self.player_x, self.player_y + 1),
-            Direction::Left => (self.player_x.saturating_sub(1), self.player_y),
-            Direction::Right => (self.player_x + 1, self.player_y),
-        };
+        matches!(self.faced_tile(player.facing), Some((_, _, Tile::Campfire)))
+    }
+}
+
+/// Explored tiles are saved as a list of chunks with their bits, since JSON
+/// object keys can't be positions.
+mod explored_chunks {
+    use super::Explored;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct ExploredChunk {
+        x: usize,
+        y: usize,
+        bits: Vec<u64>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        explored: &Explored,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut chunks: Vec<ExploredChunk> = explored
+            .iter()
+            .map(|(&(x, y), bits)| ExploredChunk {
+                x,
+                y,
+                bits: bits.clone(),
+            })
+            .collect();
+        chunks.sort_by_key(|chunk| (chunk.y, chunk.x));
+        chunks.serialize(serializer)
+    }
 
-        if adjacent_x < self.width && adjacent_y < self.height {
-            if self.tiles[adjacent_y][adjacent_x] == Tile::Campfire {
-                return true;
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Explored, D::Error> {
+        let chunks = Vec::<ExploredChunk>::deserialize(deserializer)?;
+        let mut explored = Explored::new();
+        for chunk in chunks {
+            if chunk.bits.len() != super::EXPLORED_WORDS {
+                return Err(serde::de::Error::custom("explored chunk has the wrong size"));
             }
+            explored.insert((chunk.x, chunk.y), chunk.bits);
         }
-        false
+        Ok(explored)
     }
-    
-    /// Clears all player positions from the map.
-    pub fn clear_player_positions(&mut self) {
-        for row in &mut self.tiles {
-            for tile in row.iter_mut() {
-                if *tile == Tile::Player {
-                    *tile = Tile::Empty; // Or whatever represents an empty tile
-                }
+}
+
+/// The tiles strictly between `from` and `to` on a Bresenham line.
+fn line_between(
+    (from_x, from_y): (usize, usize),
+    (to_x, to_y): (usize, usize),
+) -> impl Iterator<Item = (usize, usize)> {
+    let (dx, dy) = (to_x as i64 - from_x as i64, to_y as i64 - from_y as i64);
+    let steps = dx.abs().max(dy.abs());
+    (1..steps).map(move |step| {
+        // Round to the nearest tile at each step
+        let x = from_x as i64 + (dx * step * 2 + steps * dx.signum()) / (steps * 2);
+        let y = from_y as i64 + (dy * step * 2 + steps * dy.signum()) / (steps * 2);
+        (x as usize, y as usize)
+    })
+}
+
+/// Appends one run-length encoded row and its newline to `out`.
+fn encode_row(out: &mut String, row: &[Tile]) {
+    let mut tiles = row.iter().peekable();
+    while let Some(&tile) = tiles.next() {
+        let mut run = 1;
+        while tiles.next_if(|&&next| next == tile).is_some() {
+            run += 1;
+        }
+        if run > 1 {
+            out.push_str(&run.to_string());
+        }
+        out.push(tile.to_char());
+    }
+    out.push('\n');
+}
+
+/// Decodes one run-length encoded row, which must hold exactly `width` tiles,
+/// with any entity left on them by older maps.
+fn decode_row(line: &str, width: usize) -> Option<Vec<(Tile, Option<Entity>)>> {
+    let mut row = Vec::with_capacity(width);
+    let mut run = 0;
+    for ch in line.chars() {
+        if let Some(digit) = ch.to_digit(10) {
+            run = run * 10 + digit as usize;
+            continue;
+        }
+        let count = run.max(1);
+        if row.len() + count > width {
+            return None;
+        }
+        row.resize(row.len() + count, parse_cell(ch)?);
+        run = 0;
+    }
+    (run == 0 && row.len() == width).then_some(row)
+}
+
+/// Decodes the rows of a version 1 map, one line per row.
+fn decode_rows<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    width: usize,
+    height: usize,
+) -> Result<Vec<Vec<Tile>>, MapFormatError> {
+    let mut tiles = Vec::with_capacity(height);
+    for (y, line) in lines.enumerate() {
+        let mut row = Vec::with_capacity(width);
+        let mut run = 0;
+        for ch in line.chars() {
+            if let Some(digit) = ch.to_digit(10) {
+                run = run * 10 + digit as usize;
+                continue;
+            }
+            let (tile, _) = parse_cell(ch).ok_or(MapFormatError::BadRow(y))?;
+            let count = run.max(1);
+            if row.len() + count > width {
+                return Err(MapFormatError::SizeMismatch {
+                    expected: (width, height),
+                    found: (row.len() + count, y + 1),
+                });
             }
+            row.resize(row.len() + count, tile);
+            run = 0;
         }
+        if run != 0 {
+            return Err(MapFormatError::BadRow(y));
+        }
+        if row.len() != width {
+            return Err(MapFormatError::SizeMismatch {
+                expected: (width, height),
+                found: (row.len(), y + 1),
+            });
+        }
+        tiles.push(row);
+    }
+    if tiles.len() != height {
+        return Err(MapFormatError::SizeMismatch {
+            expected: (width, height),
+            found: (width, tiles.len()),
+        });
     }
+    Ok(tiles)
 }
 
 impl fmt::Display for Tile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let symbol = match self {
-            Tile::Empty => '.',
-            Tile::Player => 'P',
-            Tile::Tree => 't',
-            Tile::Rock => 'r',
-            Tile::Campfire => '#',
-        };
-        write!(f, "{}", symbol)
+        write!(f, "{}", self.to_char())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn rows(map: &Map) -> Vec<Vec<Tile>> {
+        (0..map.height)
+            .map(|y| (0..map.width).map(|x| map.tile(x, y)).collect())
+            .collect()
+    }
+
+    // The one char per tile map.txt plus the JSON tile grid saves used to carry
+    fn legacy_size(map: &Map) -> usize {
+        let tiles = rows(map);
+        let map_txt: usize = tiles.iter().map(|row| row.len() + 1).sum();
+        let json_tiles = serde_json::to_string(&tiles).unwrap().len();
+        map_txt + json_tiles
+    }
+
+    #[test]
+    fn encoded_map_round_trips() {
+        let mut map = Map::new(300, 300, 42);
+        map.campfire_x = 120;
+        map.campfire_y = 7;
+        map.set_tile(120, 7, Tile::Campfire);
+        map.set_tile(299, 299, Tile::Rock);
+
+        let encoded = map.serialize_map();
+        assert!(Map::is_encoded_map(&encoded));
+        let decoded = Map::deserialize_map(&encoded, map.player_x, map.player_y).unwrap();
+
+        assert_eq!(decoded.width, 300);
+        assert_eq!(decoded.height, 300);
+        assert_eq!(decoded.seed, map.seed);
+        assert_eq!((decoded.campfire_x, decoded.campfire_y), (120, 7));
+        assert_eq!(rows(&decoded), rows(&map));
+    }
+
+    #[test]
+    fn version_1_maps_are_still_read() {
+        let decoded = Map::deserialize_map("RPGMAP 1 3 2 0 1\n3.\nt#r\n", 1, 0).unwrap();
+
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!((decoded.campfire_x, decoded.campfire_y), (0, 1));
+        assert_eq!((decoded.player_x, decoded.player_y), (1, 0));
+        assert_eq!(
+            rows(&decoded),
+            vec![
+                vec![Tile::Empty, Tile::Empty, Tile::Empty],
+                vec![Tile::Tree, Tile::Campfire, Tile::Rock]
+            ]
+        );
+        // Nothing can regenerate a fixed size map, so all of it is kept
+        assert!(decoded.serialize_map().contains("CHUNK 0 0"));
+    }
+
+    #[test]
+    fn water_blocks_movement() {
+        let mut map = Map::deserialize_map("RPGMAP 1 3 1 0 0\n.~f\n", 0, 0).unwrap();
+        map.move_player(&Direction::Right);
+        assert_eq!((map.player_x, map.player_y), (0, 0));
+
+        map.set_tile(1, 0, Tile::Sand);
+        map.move_player(&Direction::Right);
+        map.move_player(&Direction::Right);
+        assert_eq!((map.player_x, map.player_y), (1, 0));
+        assert_eq!(map.faced_tile(Direction::Right).unwrap().2, Tile::FishingSpot);
+    }
+
+    #[test]
+    fn trees_and_rocks_block_sight() {
+        let mut map = Map::deserialize_map("RPGMAP 1 7 3 0 0\n.......\n..t....\n.......\n", 0, 1)
+            .unwrap();
+        assert!(map.is_visible(2, 1));
+        assert!(!map.is_visible(3, 1));
+        assert!(!map.is_visible(6, 1));
+        assert!(map.is_visible(3, 0));
+        assert!(!map.is_explored(6, 1));
+
+        // Once the tree is out of the way the tiles behind it are seen and remembered
+        map.set_tile(2, 1, Tile::Stump);
+        map.reveal_around_player();
+        map.set_tile(2, 1, Tile::Rock);
+        assert!(!map.is_visible(6, 1));
+        assert!(map.is_explored(6, 1));
+    }
+
+    #[test]
+    fn unexplored_tiles_are_blank() {
+        let mut map = Map::deserialize_map("RPGMAP 1 3 1 0 0\n.r.\n", 0, 0).unwrap();
+        assert_eq!(map.render(), "P r   \n");
+
+        map.set_tile(1, 0, Tile::DepletedRock);
+        map.reveal_around_player();
+        map.set_tile(1, 0, Tile::Rock);
+        // The tile behind the rock is remembered, but dimmed
+        let rendered = map.render();
+        assert!(rendered.starts_with("P r "));
+        assert!(rendered.contains(&format!("{}", ".".dim())));
+    }
+
+    #[test]
+    fn world_map_shows_the_dominant_explored_tile() {
+        let map = Map::from_layout(
+            "test",
+            &[
+                "tt.\"\"\"\"\"",
+                "tt..\"\"\"\"",
+                "t....\"\"\"",
+                "...P.\"~~",
+                "........",
+                ".#......",
+                "........",
+                "........",
+            ],
+            Vec::new(),
+        );
+        // The map was never walked, so only the player's square is drawn
+        assert_eq!(map.render_world_map(0, 0, 4, 4), "P   \n    \n");
+
+        let mut map = map;
+        map.reveal_around_player();
+        assert_eq!(map.render_world_map(0, 0, 4, 4), "P \" \n# . \n");
+        // Scrolling past the edge of the map shows what is left of it
+        assert_eq!(map.render_world_map(7, 7, 1, 1), ". \n");
+    }
+
+    #[test]
+    fn world_map_marks_doors_that_lead_somewhere() {
+        let mut map = Map::from_layout(
+            "test",
+            &[
+                "==+=========",
+                "=..........=",
+                "=..........=",
+                "=........P.=",
+                "============",
+            ],
+            vec![Portal {
+                x: 2,
+                y: 0,
+                target: OVERWORLD.to_string(),
+                arrival: None,
+            }],
+        );
+        map.set_tile(6, 0, Tile::Door);
+        map.reveal_around_player();
+        // The locked door at (6, 0) is just part of the wall
+        assert_eq!(map.render_world_map(0, 0, 3, 1), "+ . P \n");
+    }
+
+    #[test]
+    fn walking_over_a_campfire_leaves_it_burning() {
+        let mut map = Map::from_layout("test", &["P#."], Vec::new());
+        map.move_player(&Direction::Right);
+        assert_eq!((map.player_x, map.player_y), (1, 0));
+        map.move_player(&Direction::Right);
+        assert_eq!(map.tile(1, 0), Tile::Campfire);
+        assert_eq!(map.tile(0, 0), Tile::Empty);
+        assert_eq!(map.render(), ". # P \n");
+    }
+
+    #[test]
+    fn entities_stand_on_the_ground_and_block_the_way() {
+        let mut map = Map::from_layout("test", &["=====", "=PE$=", "====="], Vec::new());
+        assert_eq!(map.tile(2, 1), Tile::Empty);
+        assert_eq!(map.entity_at(2, 1), Some(Entity::Enemy));
+        assert_eq!(map.render().lines().nth(1), Some("= P E $ = "));
+
+        assert!(!map.move_player(&Direction::Right));
+        assert_eq!(map.player_x, 1);
+        assert_eq!(map.remove_entity(2, 1), Some(Entity::Enemy));
+        assert!(map.move_player(&Direction::Right));
+        assert_eq!(map.player_x, 2);
+        // Walls and the map edge stop the player too
+        assert!(!map.move_player(&Direction::Up));
+        // Entities aren't written with the tiles
+        assert!(!map.serialize_map().contains('E'));
+    }
+
+    #[test]
+    fn entities_in_older_maps_are_lifted_off_the_grid() {
+        let mut encoded = String::from("RPGMAP 2 3 1 0 0 0\nCHUNK 0 0\nPE$27.\n");
+        encoded.push_str(&"30.\n".repeat(CHUNK_SIZE - 1));
+        let map = Map::deserialize_map(&encoded, 0, 0).unwrap();
+
+        assert_eq!(rows(&map), vec![vec![Tile::Empty; 3]]);
+        assert_eq!(map.entity_at(1, 0), Some(Entity::Enemy));
+        assert_eq!(map.entity_at(2, 0), Some(Entity::Chest));
+    }
+
+    #[test]
+    fn encoded_map_rejects_malformed_data() {
+        assert_eq!(
+            Map::deserialize_map("garbage", 0, 0).unwrap_err(),
+            MapFormatError::BadHeader
+        );
+        assert_eq!(
+            Map::deserialize_map("RPGMAP 99 1 1 0 0\n.\n", 0, 0).unwrap_err(),
+            MapFormatError::UnsupportedVersion(99)
+        );
+        assert_eq!(
+            Map::deserialize_map("RPGMAP 1 3 1 0 0\n2.x\n", 0, 0).unwrap_err(),
+            MapFormatError::BadRow(0)
+        );
+        assert_eq!(
+            Map::deserialize_map("RPGMAP 1 3 2 0 0\n3.\n4.\n", 0, 0).unwrap_err(),
+            MapFormatError::SizeMismatch {
+                expected: (3, 2),
+                found: (4, 2)
+            }
+        );
+        assert_eq!(
+            Map::deserialize_map("RPGMAP 2 60 60 0 0\n.\n", 0, 0).unwrap_err(),
+            MapFormatError::BadHeader
+        );
+        assert_eq!(
+            Map::deserialize_map("RPGMAP 2 60 60 0 0 7\nCHUNK 2 0\n", 0, 0).unwrap_err(),
+            MapFormatError::BadChunk(2)
+        );
+        // Far too big to turn into a tile position
+        let huge = format!("RPGMAP 2 60 60 0 0 7\nCHUNK {} 0\n", usize::MAX);
+        assert_eq!(
+            Map::deserialize_map(&huge, 0, 0).unwrap_err(),
+            MapFormatError::BadChunk(2)
+        );
+        assert_eq!(
+            Map::deserialize_map("RPGMAP 2 60 60 0 0 7\nCHUNK 1 1\n30.\n29.\n", 0, 0)
+                .unwrap_err(),
+            MapFormatError::BadRow(4)
+        );
+    }
+
+    #[test]
+    fn encoded_map_is_smaller_than_legacy_format() {
+        let map = Map::new(300, 300, 42);
+        assert!(map.serialize_map().len() * 4 < legacy_size(&map));
+    }
+
+    #[test]
+    fn equal_seeds_generate_equal_maps() {
+        assert_eq!(
+            Map::new(300, 300, 7).serialize_map(),
+            Map::new(300, 300, 7).serialize_map()
+        );
+        assert_ne!(
+            Map::new(300, 300, 7).serialize_map(),
+            Map::new(300, 300, 8).serialize_map()
+        );
+
+        // Far away chunks too, whichever chunks were loaded on the way there
+        let mut hopped = Map::new(WORLD_SIZE, WORLD_SIZE, 7);
+        let mut teleported = Map::new(WORLD_SIZE, WORLD_SIZE, 7);
+        let (x, y) = (hopped.player_x, hopped.player_y);
+        for hop in 1..=4 {
+            hopped.place_player(x + CHUNK_SIZE * hop, y);
+        }
+        teleported.place_player(x + CHUNK_SIZE * 4, y);
+        for (far_x, far_y) in [(x + CHUNK_SIZE * 5, y + 3), (x + CHUNK_SIZE * 3 + 7, y + 40)] {
+            hopped.set_tile(far_x, far_y, Tile::Campfire);
+            teleported.set_tile(far_x, far_y, Tile::Campfire);
+        }
+        assert_eq!(hopped.serialize_map(), teleported.serialize_map());
+    }
+
+    #[test]
+    fn seeds_can_be_typed_as_numbers_or_words() {
+        assert_eq!(parse_seed(" 12345 \n"), 12345);
+        assert_eq!(parse_seed("goblin camp"), parse_seed("goblin camp"));
+        assert_ne!(parse_seed("goblin camp"), parse_seed("lost sword"));
+    }
+
+    #[test]
+    fn only_modified_chunks_are_saved() {
+        let mut map = Map::new(WORLD_SIZE, WORLD_SIZE, 42);
+        assert_eq!(map.serialize_map().matches("CHUNK").count(), 1);
+
+        for _ in 0..CHUNK_SIZE * 3 {
+            map.place_player(map.player_x + 1, map.player_y);
+        }
+        assert_eq!(map.serialize_map().matches("CHUNK").count(), 1);
+
+        map.set_tile(map.player_x, map.player_y + 2, Tile::Campfire);
+        assert_eq!(map.serialize_map().matches("CHUNK").count(), 2);
+    }
+
+    #[test]
+    fn far_chunks_are_unloaded_and_regenerate_the_same() {
+        let mut map = Map::new(WORLD_SIZE, WORLD_SIZE, 42);
+        let (start_x, start_y) = (map.player_x, map.player_y);
+        let nearby = (start_x + CHUNK_SIZE + 3, start_y + 4);
+        let before = map.tile(nearby.0, nearby.1);
+        let side = 2 * LOADED_CHUNK_RADIUS + 1;
+        assert_eq!(map.chunks.len(), side * side);
+
+        map.place_player(start_x + CHUNK_SIZE * 20, start_y);
+        // The spawn chunk stays since the campfire modified it
+        assert_eq!(map.chunks.len(), side * side + 1);
+
+        map.place_player(start_x, start_y);
+        assert_eq!(map.tile(nearby.0, nearby.1), before);
+        assert_eq!(map.tile(map.campfire_x, map.campfire_y), Tile::Campfire);
+    }
+
+    // cargo test --release -- --ignored --nocapture map_format_benchmark
+    #[test]
+    #[ignore]
+    fn map_format_benchmark() {
+        const ROUNDS: u32 = 20;
+        let map = Map::new(300, 300, 42);
+        let tiles = rows(&map);
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            let map_txt: String = tiles
+                .iter()
+                .map(|row| row.iter().map(|tile| tile.to_char()).collect::<String>() + "\n")
+                .collect();
+            let json = serde_json::to_string(&tiles).unwrap();
+            let _ = Map::deserialize_legacy_map(300, 300, &map_txt, 15, 15, 15, 16);
+            let _: Vec<Vec<Tile>> = serde_json::from_str(&json).unwrap();
+        }
+        let legacy = start.elapsed() / ROUNDS;
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            let encoded = map.serialize_map();
+            let _ = Map::deserialize_map(&encoded, 15, 15).unwrap();
+        }
+        let encoded = start.elapsed() / ROUNDS;
+
+        println!(
+            "legacy:  {:>7} bytes, {:?} per save + load",
+            legacy_size(&map),
+            legacy
+        );
+        println!(
+            "encoded: {:>7} bytes, {:?} per save + load",
+            map.serialize_map().len(),
+            encoded
+        );
     }
 }
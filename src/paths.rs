@@ -0,0 +1,206 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the data directory.
+pub const DATA_DIR_ENV: &str = "RUSTPG_DATA_DIR";
+
+/// Command line flag that overrides the data directory and the environment.
+pub const DATA_DIR_FLAG: &str = "--data-dir";
+
+/// Name of the saves folder inside the data directory. Older versions created
+/// it in the working directory.
+pub const SAVES_FOLDER: &str = "Saves";
+
+/// Reads `--data-dir <path>` or `--data-dir=<path>` from the command line
+/// arguments (without the program name).
+pub fn data_dir_flag<I: IntoIterator<Item = String>>(args: I) -> Result<Option<PathBuf>, String> {
+    let mut args = args.into_iter();
+    let mut data_dir = None;
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_FLAG {
+            match args.next() {
+                Some(path) => data_dir = Some(PathBuf::from(path)),
+                None => return Err(format!("{} needs a path", DATA_DIR_FLAG)),
+            }
+        } else if let Some(path) = arg.strip_prefix(&format!("{}=", DATA_DIR_FLAG)) {
+            data_dir = Some(PathBuf::from(path));
+        } else {
+            return Err(format!("unknown argument '{}'", arg));
+        }
+    }
+    Ok(data_dir)
+}
+
+/// Picks the data directory from the flag, then `RUSTPG_DATA_DIR`, then the
+/// platform data directory, falling back to the working directory.
+pub fn data_dir(flag: Option<PathBuf>) -> PathBuf {
+    resolve_data_dir(
+        flag,
+        env::var_os(DATA_DIR_ENV),
+        platform_data_dir(
+            env::var_os("XDG_DATA_HOME"),
+            env::var_os("HOME"),
+            env::var_os("APPDATA"),
+        ),
+    )
+}
+
+fn resolve_data_dir(
+    flag: Option<PathBuf>,
+    env_dir: Option<OsString>,
+    platform_dir: Option<PathBuf>,
+) -> PathBuf {
+    flag.or_else(|| env_dir.filter(|dir| !dir.is_empty()).map(PathBuf::from))
+        .or(platform_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// `$XDG_DATA_HOME/rustpg`, or `~/.local/share/rustpg` when it is not set.
+/// `%APPDATA%\rustpg` is used on systems without `HOME`.
+fn platform_data_dir(
+    xdg_data_home: Option<OsString>,
+    home: Option<OsString>,
+    app_data: Option<OsString>,
+) -> Option<PathBuf> {
+    // The spec says relative values must be ignored
+    let xdg_data_home = xdg_data_home
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute());
+    let base = match (xdg_data_home, home, app_data) {
+        (Some(dir), _, _) => dir,
+        (None, Some(home), _) if !home.is_empty() => Path::new(&home).join(".local").join("share"),
+        (None, _, Some(app_data)) if !app_data.is_empty() => PathBuf::from(app_data),
+        _ => return None,
+    };
+    Some(base.join("rustpg"))
+}
+
+/// Moves saves left in `./Saves` by older versions into `saves_path`.
+///
+/// Only happens while `saves_path` doesn't exist yet, so it runs once and
+/// never merges two sets of saves. Returns whether anything was moved.
+pub fn migrate_legacy_saves(legacy_path: &Path, saves_path: &Path) -> io::Result<bool> {
+    if !legacy_path.is_dir() || saves_path.exists() || same_path(legacy_path, saves_path) {
+        return Ok(false);
+    }
+    if let Some(parent) = saves_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Renaming fails across file systems, so fall back to copying
+    if fs::rename(legacy_path, saves_path).is_err() {
+        fs::create_dir_all(saves_path)?;
+        copy_dir(legacy_path, saves_path)?;
+        fs::remove_dir_all(legacy_path)?;
+    }
+    Ok(true)
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Copies everything in `from` into the existing folder `to`.
+pub fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let to_path = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&to_path)?;
+            copy_dir(&entry.path(), &to_path)?;
+        } else {
+            fs::copy(entry.path(), to_path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn flag_accepts_both_spellings() {
+        assert_eq!(data_dir_flag(args(&[])), Ok(None));
+        assert_eq!(
+            data_dir_flag(args(&["--data-dir", "/games/rpg"])),
+            Ok(Some(PathBuf::from("/games/rpg")))
+        );
+        assert_eq!(
+            data_dir_flag(args(&["--data-dir=/games/rpg"])),
+            Ok(Some(PathBuf::from("/games/rpg")))
+        );
+        assert!(data_dir_flag(args(&["--data-dir"])).is_err());
+        assert!(data_dir_flag(args(&["--verbose"])).is_err());
+    }
+
+    #[test]
+    fn flag_beats_environment_beats_platform() {
+        let platform = Some(PathBuf::from("/home/me/.local/share/rustpg"));
+        assert_eq!(
+            resolve_data_dir(
+                Some(PathBuf::from("/flag")),
+                Some("/env".into()),
+                platform.clone()
+            ),
+            PathBuf::from("/flag")
+        );
+        assert_eq!(
+            resolve_data_dir(None, Some("/env".into()), platform.clone()),
+            PathBuf::from("/env")
+        );
+        assert_eq!(
+            resolve_data_dir(None, Some("".into()), platform.clone()),
+            platform.clone().unwrap()
+        );
+        assert_eq!(resolve_data_dir(None, None, None), PathBuf::from("."));
+    }
+
+    #[test]
+    fn platform_dir_follows_xdg() {
+        assert_eq!(
+            platform_data_dir(Some("/data".into()), Some("/home/me".into()), None),
+            Some(PathBuf::from("/data/rustpg"))
+        );
+        assert_eq!(
+            platform_data_dir(Some("relative".into()), Some("/home/me".into()), None),
+            Some(PathBuf::from("/home/me/.local/share/rustpg"))
+        );
+        assert_eq!(
+            platform_data_dir(None, None, Some("C:\\AppData".into())),
+            Some(PathBuf::from("C:\\AppData").join("rustpg"))
+        );
+        assert_eq!(platform_data_dir(None, None, None), None);
+    }
+
+    #[test]
+    fn legacy_saves_are_moved_once() {
+        let root = std::env::temp_dir().join(format!("rustpg_paths_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let legacy = root.join("Saves");
+        fs::create_dir_all(legacy.join("Hero")).unwrap();
+        fs::write(legacy.join("Hero").join("character.json"), "{}").unwrap();
+        let saves_path = root.join("data").join("rustpg").join("Saves");
+
+        assert!(migrate_legacy_saves(&legacy, &saves_path).unwrap());
+        assert!(saves_path.join("Hero").join("character.json").exists());
+        assert!(!legacy.exists());
+
+        // A new ./Saves next to an existing data dir is left alone
+        fs::create_dir_all(legacy.join("Other")).unwrap();
+        assert!(!migrate_legacy_saves(&legacy, &saves_path).unwrap());
+        assert!(legacy.join("Other").exists());
+        assert!(!migrate_legacy_saves(&saves_path, &saves_path).unwrap());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
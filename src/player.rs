@@ -5,7 +5,9 @@ use crate::items::get_starting_items;
 use crate::quest::Quest;
 use crate::items::{Item, ItemType};
 use crate::items::create_items;
-use crate::map::{Map, Direction};
+use crate::map::{Map, Direction, OVERWORLD};
+use crate::world::Areas;
+use crate::status::{StatusEffects, StatusKind};
 use std::io::{self, Write};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,6 +25,16 @@ pub struct Player {
     pub active_quest: Option<Quest>,
     pub in_combat: bool,
     pub facing: Direction,
+    pub play_time: u64, // Seconds played across all sessions
+    pub status_effects: StatusEffects,
+    pub respawn_point: Option<RespawnPoint>, // The overworld campfire when unset
+}
+
+/// The campfire at `(x, y)` on the map named `map` that the player comes
+/// back to after dying.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RespawnPoint {
+    pub map: String,
     pub x: usize,
     pub y: usize,
 }
@@ -43,8 +55,9 @@ impl Player {
             active_quest: None,
             in_combat: false,
             facing: Direction::Down, // Initially facing south
-            x: 0, // Default position
-            y: 0,
+            play_time: 0,
+            status_effects: StatusEffects::default(),
+            respawn_point: None,
         };
         player.add_starting_items();
         player
@@ -71,6 +84,7 @@ impl Player {
         }
     }
 
+    #[allow(dead_code)]
     pub fn display_inventory(&self) {
         println!("Inventory:");
         for (item_id, quantity) in &self.inventory {
@@ -80,6 +94,7 @@ impl Player {
         }
     }
 
+    #[allow(dead_code)]
     pub fn take_damage(&mut self, amount: i32) {
         self.health -= amount;
         if self.health <= 0 {
@@ -88,6 +103,7 @@ impl Player {
         }
     }
 
+    #[allow(dead_code)]
     pub fn add_experience(&mut self, amount: i32) {
         self.experience += amount;
         if self.experience >= 100 {
@@ -95,6 +111,7 @@ impl Player {
         }
     }
 
+    #[allow(dead_code)]
     pub fn level_up(&mut self) {
         self.level += 1;
         self.health = self.max_health; // Restore health to max on level up
@@ -110,7 +127,7 @@ impl Player {
 
         // Render the top menu
         println!("(w/a/s/d) move | (status) player status | (quests) view quests");
-        println!("(i) inventory | (m) menu | (q) quit");
+        println!("(i) inventory | (e) interact | (m) menu | (q) quit");
         println!();
 
         // Left Column: Health, Level, Experience
@@ -118,6 +135,7 @@ impl Player {
             format!("Health:    {}/{}", self.health, self.max_health),
             format!("Level:     {}", self.level),
             format!("Experience: {}", self.experience),
+            format!("Effects:   {}", self.status_effects),
             String::from("Skills:"),
         ];
 
@@ -167,11 +185,22 @@ impl Player {
         status
     }
 
+    /// Passes a turn for the player's status effects, applying what they do
+    /// to health. Returns the health change of each effect that had one.
+    pub fn tick_status_effects(&mut self) -> Vec<(StatusKind, i32)> {
+        let changes = self.status_effects.tick();
+        for (_, change) in &changes {
+            self.health = (self.health + change).clamp(0, self.max_health);
+        }
+        changes
+    }
+
     pub fn interact(&self, map: &Map) -> Option<String> {
         map.interact(self)
     }
 
     // Train a skill by adding experience to it
+    #[allow(dead_code)]
     pub fn train_skill(&mut self, skill_name: &str, xp_gain: f32) {
         if let Some(skill) = self.skills.get_mut(skill_name) {
             skill.add_experience(xp_gain as f64);
@@ -189,6 +218,7 @@ impl Player {
     }
 
     // Method to display only consumable items
+    #[allow(dead_code)]
     pub fn display_consumables(&self) {
         println!("\n[Consumable Items]");
         let items = create_items();
@@ -233,26 +263,46 @@ impl Player {
         self.skills.values().map(|skill| skill.level.min(99)).sum()
     }
 
-    pub fn respawn(&mut self, map: &mut Map) {
+    // Combat level from Hitpoints plus the stronger of melee (Attack + Strength) or Magic
+    pub fn combat_level(&self) -> i32 {
+        let level = |name: &str| self.skills.get(name).map_or(1, |skill| skill.level.min(99));
+        let melee = level("Attack") + level("Strength");
+        let magic = level("Magic") * 2;
+        (level("Hitpoints") + melee.max(magic)) / 3
+    }
+
+    /// Brings the player back at full health next to their respawn point,
+    /// taking them to its map through `areas` if they fell on another one.
+    pub fn respawn(&mut self, map: &mut Map, areas: &mut Areas) {
         self.health = self.max_health;
         self.in_combat = false;
+        self.status_effects.clear();
         self.facing = Direction::Down; // Reset facing direction
 
-        map.player_x = map.campfire_x;
-        
-        // Safely handle player_y to prevent underflow
-        if map.campfire_y > 0 {
-            map.player_y = map.campfire_y - 1;
-        } else {
-            map.player_y = 0; // Default to top row if campfire_y is 0
+        let home = self
+            .respawn_point
+            .as_ref()
+            .map_or(OVERWORLD, |point| point.map.as_str());
+        if map.name != home {
+            areas.go_to(map, home);
         }
+        let (campfire_x, campfire_y) = match &self.respawn_point {
+            Some(point) if point.map == map.name => (point.x, point.y),
+            _ => (map.campfire_x, map.campfire_y),
+        };
+
+        // Safely handle player_y to prevent underflow
+        let mut respawn_y = campfire_y.saturating_sub(1);
 
         // Ensure the new position is valid
-        if map.player_y >= map.height {
-            map.player_y = map.height - 1;
+        if respawn_y >= map.height {
+            respawn_y = map.height - 1;
         }
+
+        map.place_player(campfire_x, respawn_y);
     }
 
+    #[allow(dead_code)]
     pub fn add_experience_to_skill(&mut self, skill_name: &str, amount: f32) {
         if let Some(skill) = self.skills.get_mut(skill_name) {
             skill.add_experience(amount as f64);
@@ -261,10 +311,88 @@ impl Player {
             println!("Skill not found: {}", skill_name);
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Tile, TOWN};
+    use crate::status::StatusEffect;
+
+    #[test]
+    fn respawn_moves_player_next_to_campfire() {
+        let mut map = Map::new(30, 30, 42);
+        let start = (map.player_x, map.player_y);
+        let mut player = Player::new();
+        map.campfire_x = 20;
+        map.campfire_y = 22;
+        map.set_tile(20, 22, Tile::Campfire);
+        player.health = 0;
+        player
+            .status_effects
+            .apply(StatusEffect::new(StatusKind::Poison, 2, 3));
+
+        player.respawn(&mut map, &mut Areas::default());
+
+        assert_eq!(player.health, player.max_health);
+        assert!(player.status_effects.is_empty());
+        assert_eq!((map.player_x, map.player_y), (20, 21));
+        assert_ne!((map.player_x, map.player_y), start);
+        assert_eq!(map.tile(20, 22), Tile::Campfire);
+        assert!(map.is_adjacent_and_facing_campfire(&player));
+    }
+
+    #[test]
+    fn respawn_takes_the_player_home_from_another_area() {
+        let mut areas = Areas::default();
+        let mut map = Map::new(60, 60, 42);
+        let gate = map.portals[0].clone();
+        map.move_player(&Direction::Right);
+        areas.travel(&mut map, gate.x, gate.y).unwrap();
+        assert_eq!(map.tile(14, 7), Tile::Campfire);
+        let mut player = Player::new();
+        player.respawn_point = Some(RespawnPoint {
+            map: TOWN.to_string(),
+            x: 14,
+            y: 7,
+        });
+
+        // Falling on the overworld brings the player back to the town campfire
+        areas.travel(&mut map, 14, 13).unwrap();
+        assert_eq!(map.name, OVERWORLD);
+        player.health = 0;
+        player.respawn(&mut map, &mut areas);
+
+        assert_eq!(map.name, TOWN);
+        assert_eq!((map.player_x, map.player_y), (14, 6));
+        assert_eq!(player.health, player.max_health);
+        assert!(areas.iter().any(|area| area.is_overworld()));
+    }
 
-    // Method to set position
-    pub fn set_position(&mut self, x: usize, y: usize) {
-        self.x = x;
-        self.y = y;
+    #[test]
+    fn status_effects_change_health_each_turn() {
+        let mut player = Player::new();
+        player.health = 50;
+        player
+            .status_effects
+            .apply(StatusEffect::new(StatusKind::Poison, 2, 2));
+        player
+            .status_effects
+            .apply(StatusEffect::new(StatusKind::Regen, 100, 1));
+
+        assert_eq!(
+            player.tick_status_effects(),
+            vec![(StatusKind::Poison, -2), (StatusKind::Regen, 100)]
+        );
+        assert_eq!(player.health, player.max_health);
+        player.tick_status_effects();
+        assert_eq!(player.health, player.max_health - 2);
+
+        player.health = 1;
+        player
+            .status_effects
+            .apply(StatusEffect::new(StatusKind::Bleed, 5, 1));
+        player.tick_status_effects();
+        assert_eq!(player.health, 0);
     }
 }
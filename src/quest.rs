@@ -30,7 +30,3 @@ impl Quest {
 pub fn starting_quest() -> Quest {
     Quest::new(1, "Starting Off", "Explore the map and defeat an enemy.")
 }
-
-pub fn sample_quests() -> Vec<Quest> {
-    vec![starting_quest()]
-}
\ No newline at end of file
@@ -0,0 +1,1504 @@
+use crate::archive::{check_save_name, ArchiveError};
+use crate::map::{Map, MapFormatError, OVERWORLD};
+use crate::player::Player;
+use crate::world::Areas;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the `character.json` layout written by `save_game`.
+///
+/// Bump this whenever the saved layout changes (including new fields on
+/// `Player`, `Map` or `Quest`) and append a matching entry to `MIGRATIONS`.
+pub const SAVE_VERSION: u32 = 12;
+
+/// Number of rotating backups kept per character when `RUSTPG_SAVE_BACKUPS`
+/// is not set.
+pub const DEFAULT_BACKUP_COUNT: usize = 3;
+
+pub const CHARACTER_FILE: &str = "character.json";
+pub const MAP_FILE: &str = "map.txt";
+pub const METADATA_FILE: &str = "meta.json";
+const SAVE_FILES: [&str; 3] = [CHARACTER_FILE, MAP_FILE, METADATA_FILE];
+
+/// Folder in a save that holds the tiles of every map except the overworld,
+/// which stays in `map.txt`.
+pub const AREAS_FOLDER: &str = "maps";
+
+/// Present while a save is being committed; see `write_save_files`.
+const COMMIT_MARKER: &str = "save.commit";
+
+/// Upgrades a raw save from one version to the next.
+type Migration = fn(&mut Value);
+
+/// Migration chain, where `MIGRATIONS[n]` upgrades a version `n + 1` save to
+/// version `n + 2`.
+const MIGRATIONS: [Migration; (SAVE_VERSION - 1) as usize] = [
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+    migrate_v8_to_v9,
+    migrate_v9_to_v10,
+    migrate_v10_to_v11,
+    migrate_v11_to_v12,
+];
+
+/// Everything that can go wrong while writing or reading a save folder.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    Map(MapFormatError),
+    Archive(ArchiveError),
+    MissingFile(PathBuf),
+    VersionMismatch {
+        found: u32,
+        supported: u32,
+    },
+    MapSizeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    MissingMap(String),
+    /// A map name that would lead outside the areas folder.
+    InvalidMapName(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "I/O error: {}", e),
+            SaveError::Parse(e) => write!(f, "could not parse save: {}", e),
+            SaveError::Map(e) => write!(f, "could not parse map: {}", e),
+            SaveError::Archive(e) => write!(f, "could not import archive: {}", e),
+            SaveError::MissingFile(path) => write!(f, "missing file: {}", path.display()),
+            SaveError::VersionMismatch { found, supported } => write!(
+                f,
+                "save version {} is newer than supported version {}",
+                found, supported
+            ),
+            SaveError::MapSizeMismatch { expected, found } => write!(
+                f,
+                "map is {}x{} but the save expects {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            SaveError::MissingMap(name) => write!(f, "the save has no map named '{}'", name),
+            SaveError::InvalidMapName(name) => write!(f, "'{}' is not a valid map name", name),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<MapFormatError> for SaveError {
+    fn from(e: MapFormatError) -> Self {
+        match e {
+            MapFormatError::SizeMismatch { expected, found } => {
+                SaveError::MapSizeMismatch { expected, found }
+            }
+            e => SaveError::Map(e),
+        }
+    }
+}
+
+impl From<ArchiveError> for SaveError {
+    fn from(e: ArchiveError) -> Self {
+        SaveError::Archive(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Parse(e)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CharacterSave {
+    pub save_version: u32,
+    pub character_name: String,
+    pub player: Player,
+    pub game_map: Map,
+    pub areas: Vec<Map>,
+    pub current_map: String,
+}
+
+/// Number of rotating backups to keep, from `RUSTPG_SAVE_BACKUPS` or
+/// `DEFAULT_BACKUP_COUNT`.
+pub fn backup_count() -> usize {
+    env::var("RUSTPG_SAVE_BACKUPS")
+        .ok()
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(DEFAULT_BACKUP_COUNT)
+}
+
+/// Small summary written next to each save so the save list can be shown
+/// without loading the character and map.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SaveMetadata {
+    pub character_name: String,
+    pub total_level: i32,
+    pub combat_level: i32,
+    pub play_time: u64,
+    pub last_location: String,
+    pub completed_quests: usize,
+    pub game_version: String,
+    pub build: String,
+}
+
+impl SaveMetadata {
+    pub fn new(player: &Player, game_map: &Map, character_name: &str) -> Self {
+        SaveMetadata {
+            character_name: character_name.to_string(),
+            total_level: player.total_level(),
+            combat_level: player.combat_level(),
+            play_time: player.play_time,
+            last_location: if game_map.is_overworld() {
+                format!("({}, {})", game_map.player_x, game_map.player_y)
+            } else {
+                format!(
+                    "{} ({}, {})",
+                    game_map.name, game_map.player_x, game_map.player_y
+                )
+            },
+            completed_quests: player.quests.iter().filter(|q| q.is_completed()).count(),
+            game_version: option_env!("VERSION")
+                .unwrap_or("unknown version")
+                .to_string(),
+            build: option_env!("BUILD_NUMBER")
+                .unwrap_or("unknown build")
+                .to_string(),
+        }
+    }
+
+    /// Play time as `HHh MMm`.
+    pub fn play_time_display(&self) -> String {
+        format!(
+            "{}h {:02}m",
+            self.play_time / 3600,
+            (self.play_time / 60) % 60
+        )
+    }
+}
+
+/// Saves a game that only has the overworld.
+pub fn save_game(
+    player: &Player,
+    game_map: &Map,
+    save_folder: &Path,
+    character_name: &str,
+) -> Result<(), SaveError> {
+    save_world(
+        player,
+        game_map,
+        &Areas::default(),
+        save_folder,
+        character_name,
+    )
+}
+
+/// Saves the player, the map they are in and every other map in `areas`.
+pub fn save_world(
+    player: &Player,
+    game_map: &Map,
+    areas: &Areas,
+    save_folder: &Path,
+    character_name: &str,
+) -> Result<(), SaveError> {
+    save_game_with_backups(
+        player,
+        game_map,
+        areas,
+        save_folder,
+        character_name,
+        backup_count(),
+    )
+}
+
+pub fn save_game_with_backups(
+    player: &Player,
+    game_map: &Map,
+    areas: &Areas,
+    save_folder: &Path,
+    character_name: &str,
+    backups: usize,
+) -> Result<(), SaveError> {
+    let maps: Vec<&Map> = std::iter::once(game_map)
+        .chain(areas.iter().filter(|map| map.name != game_map.name))
+        .collect();
+    let overworld = maps
+        .iter()
+        .find(|map| map.is_overworld())
+        .ok_or_else(|| SaveError::MissingMap(OVERWORLD.to_string()))?;
+    let others: Vec<&Map> = maps
+        .iter()
+        .filter(|map| !map.is_overworld())
+        .copied()
+        .collect();
+    for map in &others {
+        check_map_name(&map.name)?;
+    }
+
+    let character_save = CharacterSave {
+        save_version: SAVE_VERSION,
+        character_name: character_name.to_string(),
+        player: player.clone(),
+        game_map: (*overworld).clone(),
+        areas: others.iter().map(|&map| map.clone()).collect(),
+        current_map: game_map.name.clone(),
+    };
+
+    let metadata = SaveMetadata::new(player, game_map, character_name);
+    let area_files: Vec<(String, String)> = others
+        .iter()
+        .map(|map| (area_file(&map.name), map.serialize_map()))
+        .collect();
+
+    write_save_files(
+        save_folder,
+        [
+            serde_json::to_string(&character_save)?,
+            overworld.serialize_map(),
+            serde_json::to_string(&metadata)?,
+        ],
+        &area_files,
+        backups,
+    )
+}
+
+/// Path of the tiles of the map named `name`, relative to the save folder.
+pub fn area_file(name: &str) -> String {
+    format!("{}/{}.txt", AREAS_FOLDER, name)
+}
+
+/// Writes `character.json`, `map.txt` and `meta.json` together, along with
+/// the tiles of every other map.
+///
+/// All files are first staged as `.tmp` files and synced, then the commit
+/// marker is created. From that point on the new pair wins: backups are
+/// rotated and the temp files are renamed into place. If the game dies
+/// halfway, `recover_interrupted_save` either rolls the write forward (marker
+/// present) or throws the half-written temp files away (marker missing).
+/// Backups keep the three main files and the area files saved with them.
+fn write_save_files(
+    save_folder: &Path,
+    contents: [String; 3],
+    area_files: &[(String, String)],
+    backups: usize,
+) -> Result<(), SaveError> {
+    recover_interrupted_save(save_folder)?;
+
+    for (file, data) in SAVE_FILES.iter().zip(contents.iter()) {
+        write_synced(&temp_path(save_folder, file), data.as_bytes())?;
+    }
+    if !area_files.is_empty() {
+        fs::create_dir_all(save_folder.join(AREAS_FOLDER))?;
+    }
+    for (file, data) in area_files {
+        write_synced(&temp_path(save_folder, file), data.as_bytes())?;
+    }
+    write_synced(&save_folder.join(COMMIT_MARKER), b"")?;
+    sync_folder(save_folder);
+
+    rotate_backups(save_folder, backups)?;
+    finish_commit(save_folder)
+}
+
+fn write_synced(path: &Path, data: &[u8]) -> Result<(), SaveError> {
+    let mut file = File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Flushes renames in `save_folder` to disk where the platform allows it.
+fn sync_folder(save_folder: &Path) {
+    if let Ok(folder) = File::open(save_folder) {
+        let _ = folder.sync_all();
+    }
+}
+
+fn temp_path(save_folder: &Path, file: &str) -> PathBuf {
+    save_folder.join(format!("{}.tmp", file))
+}
+
+fn backup_path(save_folder: &Path, file: &str, number: usize) -> PathBuf {
+    save_folder.join(format!("{}.{}", file, number))
+}
+
+fn finish_commit(save_folder: &Path) -> Result<(), SaveError> {
+    for file in SAVE_FILES {
+        let temp = temp_path(save_folder, file);
+        if temp.exists() {
+            fs::rename(&temp, save_folder.join(file))?;
+        }
+    }
+    for temp in staged_area_files(save_folder)? {
+        fs::rename(&temp, temp.with_extension(""))?;
+    }
+    fs::remove_file(save_folder.join(COMMIT_MARKER))?;
+    sync_folder(save_folder);
+    Ok(())
+}
+
+/// Area files staged by an unfinished `write_save_files`.
+fn staged_area_files(save_folder: &Path) -> Result<Vec<PathBuf>, SaveError> {
+    files_with_extension(&save_folder.join(AREAS_FOLDER), "tmp")
+}
+
+/// Files in `folder` ending in `.extension`, or none if there is no folder.
+fn files_with_extension(folder: &Path, extension: &str) -> Result<Vec<PathBuf>, SaveError> {
+    if !folder.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|found| found == extension) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Completes or discards a save that was interrupted part way through
+/// `write_save_files`.
+pub fn recover_interrupted_save(save_folder: &Path) -> Result<(), SaveError> {
+    if save_folder.join(COMMIT_MARKER).exists() {
+        return finish_commit(save_folder);
+    }
+    for file in SAVE_FILES {
+        let temp = temp_path(save_folder, file);
+        if temp.exists() {
+            fs::remove_file(temp)?;
+        }
+    }
+    for temp in staged_area_files(save_folder)? {
+        fs::remove_file(temp)?;
+    }
+    Ok(())
+}
+
+/// Shifts `file.1` to `file.2` and so on, dropping anything past `backups`,
+/// then moves the current save files to `.1`. The area files go the same
+/// way as a whole, from `maps/` to `maps.1/`.
+fn rotate_backups(save_folder: &Path, backups: usize) -> Result<(), SaveError> {
+    if backups == 0 {
+        return Ok(());
+    }
+    for file in SAVE_FILES {
+        let oldest = backup_path(save_folder, file, backups);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for number in (1..backups).rev() {
+            let from = backup_path(save_folder, file, number);
+            if from.exists() {
+                fs::rename(from, backup_path(save_folder, file, number + 1))?;
+            }
+        }
+        let current = save_folder.join(file);
+        if current.exists() {
+            fs::rename(current, backup_path(save_folder, file, 1))?;
+        }
+    }
+
+    for number in (1..=backups).rev() {
+        let backup = backup_path(save_folder, AREAS_FOLDER, number);
+        if backup.is_dir() {
+            fs::remove_dir_all(&backup)?;
+        }
+        if number > 1 {
+            let newer = backup_path(save_folder, AREAS_FOLDER, number - 1);
+            if newer.is_dir() {
+                fs::rename(newer, backup)?;
+            }
+            continue;
+        }
+        // Only the saved area files; the staged ones are for `finish_commit`
+        for file in files_with_extension(&save_folder.join(AREAS_FOLDER), "txt")? {
+            fs::create_dir_all(&backup)?;
+            fs::rename(&file, backup.join(file.file_name().unwrap_or_default()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Backup numbers for which both save files exist, newest (`1`) first.
+pub fn list_backups(save_folder: &Path) -> Vec<usize> {
+    let prefix = format!("{}.", CHARACTER_FILE);
+    let mut backups: Vec<usize> = fs::read_dir(save_folder)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    name.strip_prefix(&prefix)?.parse::<usize>().ok()
+                })
+                .filter(|&number| backup_path(save_folder, MAP_FILE, number).exists())
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_unstable();
+    backups
+}
+
+/// Reads backup `number` the same way `read_save` reads the current save.
+pub fn read_backup(save_folder: &Path, number: usize) -> Result<(Player, Map, String), SaveError> {
+    let (player, game_map, _, character_name) = read_save_files(
+        &backup_path(save_folder, CHARACTER_FILE, number),
+        &backup_path(save_folder, MAP_FILE, number),
+        &backup_path(save_folder, AREAS_FOLDER, number),
+    )?;
+    Ok((player, game_map, character_name))
+}
+
+/// Makes backup `number` the current save. The save being replaced is
+/// rotated into the backups like any other save, so nothing is lost.
+pub fn restore_backup(save_folder: &Path, number: usize) -> Result<(), SaveError> {
+    let (player, game_map, character_name) = read_backup(save_folder, number)?;
+    let contents = [
+        read_save_file(&backup_path(save_folder, CHARACTER_FILE, number))?,
+        read_save_file(&backup_path(save_folder, MAP_FILE, number))?,
+        serde_json::to_string(&SaveMetadata::new(&player, &game_map, &character_name))?,
+    ];
+    let mut area_files = Vec::new();
+    for path in files_with_extension(&backup_path(save_folder, AREAS_FOLDER, number), "txt")? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let file = format!("{}/{}", AREAS_FOLDER, name);
+        area_files.push((file, read_save_file(&path)?));
+    }
+    write_save_files(save_folder, contents, &area_files, backup_count())
+}
+
+/// Reads `character.json` and `map.txt` from a save folder and rebuilds the
+/// saved player, the map they are in and the character name. An
+/// interrupted save is recovered first.
+pub fn read_save(save_folder: &Path) -> Result<(Player, Map, String), SaveError> {
+    let (player, game_map, _, character_name) = read_world(save_folder)?;
+    Ok((player, game_map, character_name))
+}
+
+/// Like `read_save`, but also returns every map the player is not in.
+pub fn read_world(save_folder: &Path) -> Result<(Player, Map, Areas, String), SaveError> {
+    recover_interrupted_save(save_folder)?;
+    read_save_files(
+        &save_folder.join(CHARACTER_FILE),
+        &save_folder.join(MAP_FILE),
+        &save_folder.join(AREAS_FOLDER),
+    )
+}
+
+fn read_save_files(
+    character_file_path: &Path,
+    map_file_path: &Path,
+    areas_folder: &Path,
+) -> Result<(Player, Map, Areas, String), SaveError> {
+    // Deserialize the saved character data, upgrading older layouts first
+    let mut raw: Value = serde_json::from_str(&read_save_file(character_file_path)?)?;
+    migrate(&mut raw)?;
+    let character_data: CharacterSave = serde_json::from_value(raw)?;
+
+    let mut areas = Areas::default();
+    areas.insert(restore_map(
+        &character_data.game_map,
+        &read_save_file(map_file_path)?,
+    )?);
+    for saved_map in &character_data.areas {
+        check_map_name(&saved_map.name)?;
+        let data = read_save_file(&areas_folder.join(format!("{}.txt", saved_map.name)))?;
+        areas.insert(restore_map(saved_map, &data)?);
+    }
+
+    // current_map names the map the player was in when the game was saved
+    let current_map = areas
+        .remove(&character_data.current_map)
+        .ok_or_else(|| SaveError::MissingMap(character_data.current_map.clone()))?;
+
+    // Restore the saved player; where they stand is kept by the map
+    let mut player = character_data.player;
+    player.in_combat = false;
+
+    Ok((player, current_map, areas, character_data.character_name))
+}
+
+/// Area maps are saved as `maps/<name>.txt`, so their names must be a single
+/// plain path component like save names.
+fn check_map_name(name: &str) -> Result<(), SaveError> {
+    check_save_name(name).map_err(|_| SaveError::InvalidMapName(name.to_string()))
+}
+
+/// Rebuilds a map from its entry in `character.json` and its tiles.
+fn restore_map(saved_map: &Map, map_data_str: &str) -> Result<Map, SaveError> {
+    let mut map_data = if Map::is_encoded_map(map_data_str) {
+        let map_data = Map::deserialize_map(map_data_str, saved_map.player_x, saved_map.player_y)?;
+        if (map_data.width, map_data.height) != (saved_map.width, saved_map.height) {
+            return Err(SaveError::MapSizeMismatch {
+                expected: (saved_map.width, saved_map.height),
+                found: (map_data.width, map_data.height),
+            });
+        }
+        map_data
+    } else {
+        // Written before version 4, upgraded the next time the game is saved
+        check_map_size(map_data_str, saved_map.width, saved_map.height)?;
+        Map::deserialize_legacy_map(
+            saved_map.width,
+            saved_map.height,
+            map_data_str,
+            saved_map.player_x,
+            saved_map.player_y,
+            saved_map.campfire_x,
+            saved_map.campfire_y,
+        )
+    };
+    map_data.name = saved_map.name.clone();
+    map_data.view_radius = saved_map.view_radius;
+    map_data.world_time = saved_map.world_time;
+    map_data.regrowing = saved_map.regrowing.clone();
+    map_data.portals = saved_map.portals.clone();
+    map_data.explored = saved_map.explored.clone();
+    // Entities left in older map files were picked up while decoding them
+    map_data.entities.extend(saved_map.entities.iter().copied());
+
+    // Set the player's position on the map
+    map_data.place_player(map_data.player_x, map_data.player_y);
+
+    Ok(map_data)
+}
+
+fn read_save_file(path: &Path) -> Result<String, SaveError> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => SaveError::MissingFile(path.to_path_buf()),
+        _ => SaveError::Io(e),
+    })
+}
+
+/// Makes sure a legacy `map.txt` holds exactly `width` x `height` tiles.
+fn check_map_size(data: &str, width: usize, height: usize) -> Result<(), SaveError> {
+    let rows = data.lines().count();
+    let widest = data
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let narrowest = data
+        .lines()
+        .map(|line| line.chars().count())
+        .min()
+        .unwrap_or(0);
+
+    if rows != height || widest != width || narrowest != width {
+        let found_width = if widest != width { widest } else { narrowest };
+        return Err(SaveError::MapSizeMismatch {
+            expected: (width, height),
+            found: (found_width, rows),
+        });
+    }
+    Ok(())
+}
+
+/// Upgrades a raw `character.json` value in place to `SAVE_VERSION` and
+/// returns the version it was written with.
+pub fn migrate(raw: &mut Value) -> Result<u32, SaveError> {
+    if !raw.is_object() {
+        return Err(SaveError::Parse(serde::de::Error::custom(
+            "character file is not a JSON object",
+        )));
+    }
+
+    // Saves written before versioning was introduced have no `save_version`
+    let original_version = raw.get("save_version").and_then(Value::as_u64).unwrap_or(1) as u32;
+
+    if original_version == 0 || original_version > SAVE_VERSION {
+        return Err(SaveError::VersionMismatch {
+            found: original_version,
+            supported: SAVE_VERSION,
+        });
+    }
+
+    for migration in &MIGRATIONS[(original_version - 1) as usize..] {
+        migration(raw);
+    }
+    raw["save_version"] = Value::from(SAVE_VERSION);
+
+    Ok(original_version)
+}
+
+/// Reads the `meta.json` summary of a save. Saves written before metadata
+/// existed, or whose metadata is unreadable, get it generated from the full
+/// save and written back once.
+pub fn read_metadata(save_folder: &Path) -> Result<SaveMetadata, SaveError> {
+    recover_interrupted_save(save_folder)?;
+    let metadata_path = save_folder.join(METADATA_FILE);
+    if let Ok(metadata) = fs::read_to_string(&metadata_path) {
+        if let Ok(metadata) = serde_json::from_str(&metadata) {
+            return Ok(metadata);
+        }
+    }
+
+    let (player, game_map, character_name) = read_save(save_folder)?;
+    let metadata = SaveMetadata::new(&player, &game_map, &character_name);
+    fs::write(&metadata_path, serde_json::to_string(&metadata)?)?;
+    Ok(metadata)
+}
+
+/// Version 1 kept loose copies of the player's health, level, experience,
+/// skills, inventory, quests and position next to the full `player`. Fold
+/// the copies back into `player` and `game_map` and drop the duplicates.
+fn migrate_v1_to_v2(raw: &mut Value) {
+    let Some(save) = raw.as_object_mut() else {
+        return;
+    };
+
+    let player_x = save.remove("player_x");
+    let player_y = save.remove("player_y");
+    if let Some(map) = save.get_mut("game_map").and_then(Value::as_object_mut) {
+        if let Some(x) = player_x {
+            map.insert("player_x".to_string(), x);
+        }
+        if let Some(y) = player_y {
+            map.insert("player_y".to_string(), y);
+        }
+    }
+
+    let health = save.remove("health");
+    let level = save.remove("level");
+    let experience = save.remove("experience");
+    let inventory = save.remove("inventory");
+    let quests = save.remove("quests");
+    if let Some(player) = save.get_mut("player").and_then(Value::as_object_mut) {
+        for (field, value) in [
+            ("health", health),
+            ("level", level),
+            ("experience", experience),
+            ("inventory", inventory),
+            ("quests", quests),
+        ] {
+            if let Some(value) = value {
+                player.insert(field.to_string(), value);
+            }
+        }
+    }
+
+    // `skills` was a lossy (name, level, xp as f32) copy of `player.skills`
+    save.remove("skills");
+    // `name` duplicated `character_name`
+    save.remove("name");
+}
+
+/// Version 3 started tracking `play_time` on the player.
+fn migrate_v2_to_v3(raw: &mut Value) {
+    if let Some(player) = raw.get_mut("player").and_then(Value::as_object_mut) {
+        player.entry("play_time").or_insert(Value::from(0));
+    }
+}
+
+/// Version 4 stopped embedding the tile grid in `character.json`; the tiles
+/// only live in `map.txt` now.
+fn migrate_v3_to_v4(raw: &mut Value) {
+    if let Some(map) = raw.get_mut("game_map").and_then(Value::as_object_mut) {
+        map.remove("tiles");
+    }
+}
+
+/// Version 5 added the `world_time` clock to the map.
+fn migrate_v4_to_v5(raw: &mut Value) {
+    if let Some(map) = raw.get_mut("game_map").and_then(Value::as_object_mut) {
+        map.entry("world_time").or_insert(Value::from(0));
+    }
+}
+
+/// Version 6 split the map into chunks generated from a world seed. Older
+/// maps store every tile, so the seed they get never shows.
+fn migrate_v5_to_v6(raw: &mut Value) {
+    if let Some(map) = raw.get_mut("game_map").and_then(Value::as_object_mut) {
+        map.entry("seed").or_insert(Value::from(0));
+    }
+}
+
+/// Version 7 added felled trees and mined rocks waiting to grow back.
+fn migrate_v6_to_v7(raw: &mut Value) {
+    if let Some(map) = raw.get_mut("game_map").and_then(Value::as_object_mut) {
+        map.entry("regrowing").or_insert(Value::Array(Vec::new()));
+    }
+}
+
+/// Version 8 added towns and buildings as maps of their own. `current_map`
+/// used to hold the path of `map.txt` and now names the map the player is
+/// in, which for older saves is always the overworld.
+fn migrate_v7_to_v8(raw: &mut Value) {
+    if let Some(map) = raw.get_mut("game_map").and_then(Value::as_object_mut) {
+        map.entry("name").or_insert(Value::from(OVERWORLD));
+        map.entry("portals").or_insert(Value::Array(Vec::new()));
+    }
+    if let Some(save) = raw.as_object_mut() {
+        save.entry("areas").or_insert(Value::Array(Vec::new()));
+        save.insert("current_map".to_string(), Value::from(OVERWORLD));
+    }
+}
+
+/// Version 9 remembers which tiles of every map the player has seen. Older
+/// saves start unexplored apart from what the player can see.
+fn migrate_v8_to_v9(raw: &mut Value) {
+    if let Some(map) = raw.get_mut("game_map").and_then(Value::as_object_mut) {
+        map.entry("explored").or_insert(Value::Array(Vec::new()));
+    }
+    if let Some(areas) = raw.get_mut("areas").and_then(Value::as_array_mut) {
+        for area in areas.iter_mut().filter_map(Value::as_object_mut) {
+            area.entry("explored").or_insert(Value::Array(Vec::new()));
+        }
+    }
+}
+
+/// Version 10 took the player, guards and chests off the tile grid. Where the
+/// player stands is only kept by the map now, and guards and chests left in
+/// older map files are picked up when the map is read.
+fn migrate_v9_to_v10(raw: &mut Value) {
+    if let Some(player) = raw.get_mut("player").and_then(Value::as_object_mut) {
+        player.remove("x");
+        player.remove("y");
+    }
+    if let Some(map) = raw.get_mut("game_map").and_then(Value::as_object_mut) {
+        map.entry("entities").or_insert(Value::Array(Vec::new()));
+    }
+    if let Some(areas) = raw.get_mut("areas").and_then(Value::as_array_mut) {
+        for area in areas.iter_mut().filter_map(Value::as_object_mut) {
+            area.entry("entities").or_insert(Value::Array(Vec::new()));
+        }
+    }
+}
+
+/// Version 11 added status effects such as poison to the player, who starts
+/// without any.
+fn migrate_v10_to_v11(raw: &mut Value) {
+    if let Some(player) = raw.get_mut("player").and_then(Value::as_object_mut) {
+        player
+            .entry("status_effects")
+            .or_insert(Value::Array(Vec::new()));
+    }
+}
+
+/// Version 12 gave the player a respawn point of their own. Older saves have
+/// none, so the player respawns at the overworld campfire.
+fn migrate_v11_to_v12(raw: &mut Value) {
+    if let Some(player) = raw.get_mut("player").and_then(Value::as_object_mut) {
+        player.entry("respawn_point").or_insert(Value::Null);
+    }
+}
+
+/// An empty folder for a test to save into, unique to `name` and this test run.
+#[cfg(test)]
+pub(crate) fn temp_save_folder(name: &str) -> PathBuf {
+    let folder = env::temp_dir().join(format!("rustpg_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).expect("Failed to create temp save folder");
+    folder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::create_items;
+    use crate::map::{Direction, Entity, Tile, CHUNK_SIZE, TOWN, WORLD_SIZE};
+    use crate::player::RespawnPoint;
+    use crate::quest::starting_quest;
+    use crate::skill::initialize_skills;
+    use crate::status::{StatusEffect, StatusKind};
+    use crate::world::INN;
+
+    fn fixture_folder(version: u32) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/saves")
+            .join(format!("v{}", version))
+    }
+
+    #[test]
+    fn save_and_load_round_trip_restores_player() {
+        let save_folder = temp_save_folder("round_trip_player");
+
+        let mut player = Player::new();
+        player.skills = initialize_skills();
+        let mut game_map = Map::new(60, 60, 42);
+        player.health = 42;
+        player.max_health = 120;
+        player.experience = 77;
+        player.level = 3;
+        player.add_item_to_inventory(100022, 4);
+        player.remove_item(100019, 1);
+        player.equipped_weapon = create_items().get(&100004).cloned();
+        player.equipped_armor = create_items().get(&100009).cloned();
+        player
+            .skills
+            .get_mut("Fishing")
+            .unwrap()
+            .add_experience(500.0);
+        player.add_quest(starting_quest());
+        player.complete_quest(1);
+        player.facing = Direction::Left;
+        player.respawn_point = Some(RespawnPoint {
+            map: INN.to_string(),
+            x: 2,
+            y: 2,
+        });
+
+        save_game(&player, &game_map, &save_folder, "Round Trip").unwrap();
+        game_map.player_x += 1; // Must not leak into the loaded save
+
+        let (loaded, loaded_map, name) = read_save(&save_folder).unwrap();
+
+        assert_eq!(name, "Round Trip");
+        assert_eq!(loaded.health, 42);
+        assert_eq!(loaded.max_health, 120);
+        assert_eq!(loaded.experience, 77);
+        assert_eq!(loaded.level, 3);
+        assert_eq!(loaded.inventory, player.inventory);
+        assert_eq!(loaded.equipped_weapon, player.equipped_weapon);
+        assert_eq!(loaded.equipped_armor, player.equipped_armor);
+        assert_eq!(loaded.facing, Direction::Left);
+        assert_eq!(loaded.respawn_point, player.respawn_point);
+        assert_eq!(loaded.skills.len(), player.skills.len());
+        for (name, skill) in &player.skills {
+            let loaded_skill = &loaded.skills[name];
+            assert_eq!(loaded_skill.level, skill.level);
+            assert_eq!(loaded_skill.experience, skill.experience);
+        }
+        assert_eq!(loaded.quests.len(), 1);
+        assert_eq!(loaded.quests[0].id, 1);
+        assert!(loaded.quests[0].is_completed());
+        assert_eq!(
+            (loaded_map.player_x, loaded_map.player_y),
+            (game_map.player_x - 1, game_map.player_y)
+        );
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn save_and_load_round_trip_restores_map() {
+        let save_folder = temp_save_folder("round_trip_map");
+
+        let player = Player::new();
+        let mut game_map = Map::new(60, 60, 42);
+        game_map.move_player(&Direction::Right);
+        game_map.set_tile(5, 5, Tile::Rock);
+        game_map.set_tile(6, 5, Tile::Tree);
+        game_map.set_tile(game_map.campfire_x, game_map.campfire_y, Tile::Empty);
+        game_map.campfire_x = 40;
+        game_map.campfire_y = 41;
+        game_map.advance_time(250);
+        game_map.set_tile(40, 41, Tile::Campfire);
+
+        save_game(&player, &game_map, &save_folder, "Map Trip").unwrap();
+        let (_, loaded, _) = read_save(&save_folder).unwrap();
+
+        assert_eq!(loaded.width, game_map.width);
+        assert_eq!(loaded.height, game_map.height);
+        assert_eq!(loaded.player_x, game_map.player_x);
+        assert_eq!(loaded.player_y, game_map.player_y);
+        assert_eq!(loaded.campfire_x, 40);
+        assert_eq!(loaded.campfire_y, 41);
+        assert_eq!(loaded.view_radius, game_map.view_radius);
+        assert_eq!(loaded.world_time, 250);
+        assert_eq!(loaded.seed, game_map.seed);
+        for y in 0..game_map.height {
+            for x in 0..game_map.width {
+                assert_eq!(
+                    loaded.tile(x, y),
+                    game_map.tile(x, y),
+                    "tile ({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn world_seed_survives_a_save() {
+        let save_folder = temp_save_folder("seed");
+        let game_map = Map::new(WORLD_SIZE, WORLD_SIZE, 9_876_543_210);
+        save_game(&Player::new(), &game_map, &save_folder, "Seeded").unwrap();
+
+        let (_, loaded, _) = read_save(&save_folder).unwrap();
+        assert_eq!(loaded.seed, 9_876_543_210);
+        assert_eq!(loaded.serialize_map(), game_map.serialize_map());
+        let (x, y) = (game_map.player_x + CHUNK_SIZE * 10, game_map.player_y);
+        assert_eq!(loaded.tile(x, y), game_map.tile(x, y));
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn regrowing_tiles_survive_a_save() {
+        let save_folder = temp_save_folder("regrowing");
+        let mut game_map = Map::new(30, 30, 42);
+        game_map.set_tile(3, 3, Tile::Tree);
+        game_map.deplete_tile(3, 3, Tile::Stump, 100);
+        save_game(&Player::new(), &game_map, &save_folder, "Regrowing").unwrap();
+
+        let (_, mut loaded, _) = read_save(&save_folder).unwrap();
+        assert_eq!(loaded.tile(3, 3), Tile::Stump);
+        loaded.advance_time(100);
+        assert_eq!(loaded.tile(3, 3), Tile::Tree);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn the_map_the_player_is_in_survives_a_save() {
+        let save_folder = temp_save_folder("areas");
+        let mut areas = Areas::default();
+        let mut game_map = Map::new(60, 60, 42);
+        let gate = game_map.portals[0].clone();
+        game_map.move_player(&Direction::Right);
+        areas.travel(&mut game_map, gate.x, gate.y).unwrap();
+        game_map.place_player(10, 7);
+        save_world(&Player::new(), &game_map, &areas, &save_folder, "Visitor").unwrap();
+        assert!(save_folder.join(area_file(TOWN)).exists());
+
+        let (_, loaded, loaded_areas, _) = read_world(&save_folder).unwrap();
+        assert_eq!(loaded.name, TOWN);
+        assert_eq!((loaded.player_x, loaded.player_y), (10, 7));
+        assert_eq!(loaded.serialize_map(), game_map.serialize_map());
+        let overworld = loaded_areas.iter().find(|map| map.is_overworld()).unwrap();
+        assert_eq!(overworld.tile(gate.x, gate.y), Tile::Door);
+        assert_eq!(
+            (overworld.player_x, overworld.player_y),
+            (gate.x - 1, gate.y)
+        );
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn map_names_cannot_escape_the_save_folder() {
+        let save_folder = temp_save_folder("map_names");
+        let mut areas = Areas::default();
+        let mut game_map = Map::new(60, 60, 42);
+        let gate = game_map.portals[0].clone();
+        game_map.move_player(&Direction::Right);
+        areas.travel(&mut game_map, gate.x, gate.y).unwrap();
+        save_world(&Player::new(), &game_map, &areas, &save_folder, "Visitor").unwrap();
+
+        // An edited or imported character.json naming a map outside maps/
+        let path = save_folder.join(CHARACTER_FILE);
+        let mut raw: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        raw["current_map"] = Value::from("../../escape");
+        raw["areas"][0]["name"] = Value::from("../../escape");
+        fs::write(&path, raw.to_string()).unwrap();
+        assert!(matches!(
+            read_world(&save_folder),
+            Err(SaveError::InvalidMapName(name)) if name == "../../escape"
+        ));
+
+        game_map.name = "../escape".to_string();
+        assert!(matches!(
+            save_world(&Player::new(), &game_map, &areas, &save_folder, "Visitor"),
+            Err(SaveError::InvalidMapName(_))
+        ));
+        assert!(!save_folder.join("escape.txt.tmp").exists());
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn backups_keep_the_areas_they_were_saved_with() {
+        let save_folder = temp_save_folder("area_backups");
+        let mut areas = Areas::default();
+        let mut game_map = Map::new(60, 60, 42);
+        let gate = game_map.portals[0].clone();
+        game_map.move_player(&Direction::Right);
+        areas.travel(&mut game_map, gate.x, gate.y).unwrap();
+        game_map.set_tile(3, 3, Tile::Tree);
+        let save = |game_map: &Map| {
+            save_game_with_backups(&Player::new(), game_map, &areas, &save_folder, "Visitor", 3)
+                .unwrap()
+        };
+        save(&game_map);
+        game_map.set_tile(3, 3, Tile::Stump);
+        save(&game_map);
+        assert!(backup_path(&save_folder, AREAS_FOLDER, 1)
+            .join(format!("{}.txt", TOWN))
+            .exists());
+
+        restore_backup(&save_folder, 1).unwrap();
+        let (_, loaded, _, _) = read_world(&save_folder).unwrap();
+        assert_eq!(loaded.name, TOWN);
+        assert_eq!(loaded.tile(3, 3), Tile::Tree);
+        // The save that was replaced became backup 1, town and all
+        assert_eq!(
+            read_backup(&save_folder, 1).unwrap().1.tile(3, 3),
+            Tile::Stump
+        );
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn saves_are_written_with_current_version() {
+        let save_folder = temp_save_folder("current_version");
+        save_game(
+            &Player::new(),
+            &Map::new(30, 30, 42),
+            &save_folder,
+            "Versioned",
+        )
+        .unwrap();
+
+        let raw: Value =
+            serde_json::from_str(&fs::read_to_string(save_folder.join("character.json")).unwrap())
+                .unwrap();
+        assert_eq!(raw["save_version"], SAVE_VERSION);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn v1_fixture_migrates_to_current_version() {
+        let (player, map, name) = read_save(&fixture_folder(1)).unwrap();
+
+        assert_eq!(name, "Fixture One");
+        assert_eq!(player.health, 64);
+        assert_eq!(player.level, 2);
+        assert_eq!(player.experience, 40);
+        assert_eq!(player.inventory.get(&100022), Some(&3));
+        assert_eq!(player.quests.len(), 1);
+        assert_eq!(player.skills["Fishing"].level, 2);
+        assert_eq!((map.player_x, map.player_y), (16, 14));
+        assert_eq!((map.campfire_x, map.campfire_y), (15, 16));
+        assert_eq!(map.tile(16, 14), Tile::Empty);
+        assert_eq!(map.tile(15, 16), Tile::Campfire);
+    }
+
+    #[test]
+    fn v2_fixture_migrates_to_current_version() {
+        let (player, map, name) = read_save(&fixture_folder(2)).unwrap();
+
+        assert_eq!(name, "Fixture Two");
+        assert_eq!(player.health, 71);
+        assert_eq!(player.play_time, 0);
+        assert_eq!(player.skills["Fishing"].level, 2);
+        assert_eq!((map.player_x, map.player_y), (15, 14));
+        assert_eq!(map.tile(15, 14), Tile::Empty);
+    }
+
+    #[test]
+    fn v3_fixture_migrates_to_current_version() {
+        let (player, map, name) = read_save(&fixture_folder(3)).unwrap();
+
+        assert_eq!(name, "Fixture Three");
+        assert_eq!(player.health, 83);
+        assert_eq!(player.play_time, 4000);
+        assert_eq!((map.player_x, map.player_y), (15, 14));
+        assert_eq!((map.campfire_x, map.campfire_y), (15, 16));
+        assert_eq!(map.tile(15, 14), Tile::Empty);
+    }
+
+    #[test]
+    fn v4_fixture_migrates_to_current_version() {
+        let (player, map, name) = read_save(&fixture_folder(4)).unwrap();
+
+        assert_eq!(name, "Fixture Four");
+        assert_eq!(player.health, 90);
+        assert_eq!(player.play_time, 5000);
+        assert_eq!((map.player_x, map.player_y), (16, 14));
+        assert_eq!(map.world_time, 0);
+        assert_eq!(map.tile(16, 14), Tile::Empty);
+    }
+
+    #[test]
+    fn v5_fixture_migrates_to_current_version() {
+        let (player, map, name) = read_save(&fixture_folder(5)).unwrap();
+
+        assert_eq!(name, "Fixture Five");
+        assert_eq!(player.health, 95);
+        assert_eq!(player.play_time, 6000);
+        assert_eq!(map.world_time, 1234);
+        assert_eq!((map.width, map.height), (30, 30));
+        assert_eq!(map.tile(15, 16), Tile::Campfire);
+        assert_eq!(map.tile(16, 14), Tile::Empty);
+    }
+
+    #[test]
+    fn v6_fixture_migrates_to_current_version() {
+        let (player, map, name) = read_save(&fixture_folder(6)).unwrap();
+
+        assert_eq!(name, "Fixture Six");
+        assert_eq!(player.health, 96);
+        assert_eq!(map.world_time, 2345);
+        assert!(map.regrowing.is_empty());
+        assert_eq!(map.tile(15, 16), Tile::Campfire);
+        assert_eq!(map.tile(16, 14), Tile::Empty);
+    }
+
+    #[test]
+    fn v7_fixture_migrates_to_current_version() {
+        let (player, mut map, name) = read_save(&fixture_folder(7)).unwrap();
+
+        assert_eq!(name, "Fixture Seven");
+        assert_eq!(player.health, 97);
+        // current_map used to be a file path, every older save is in the overworld
+        assert!(map.is_overworld());
+        assert!(map.portals.is_empty());
+        assert_eq!(map.tile(3, 0), Tile::Stump);
+        map.advance_time(100);
+        assert_eq!(map.tile(3, 0), Tile::Tree);
+    }
+
+    #[test]
+    fn v8_fixture_migrates_to_current_version() {
+        let (_, map, areas, name) = read_world(&fixture_folder(8)).unwrap();
+
+        assert_eq!(name, "Fixture Eight");
+        assert_eq!(map.name, INN);
+        assert_eq!(map.tile(2, 2), Tile::Campfire);
+        // Nothing was explored before, except what the player sees on loading
+        assert!(map.is_explored(2, 2));
+        let overworld = areas.iter().find(|map| map.is_overworld()).unwrap();
+        assert!(overworld.is_explored(16, 14));
+        assert!(!overworld.is_explored(29, 29));
+    }
+
+    #[test]
+    fn v9_fixture_migrates_to_current_version() {
+        let (player, mut map, _, name) = read_world(&fixture_folder(9)).unwrap();
+
+        assert_eq!(name, "Fixture Nine");
+        assert_eq!(player.health, 97);
+        // The player, guard and chest drawn into the map file are off the grid now
+        assert_eq!((map.player_x, map.player_y), (2, 1));
+        assert_eq!(map.tile(2, 1), Tile::Empty);
+        assert_eq!(map.tile(4, 1), Tile::Empty);
+        assert_eq!(map.entity_at(4, 1), Some(Entity::Enemy));
+        assert_eq!(map.entity_at(6, 1), Some(Entity::Chest));
+        map.move_player(&Direction::Right);
+        map.move_player(&Direction::Right);
+        assert_eq!(map.player_x, 3);
+    }
+
+    #[test]
+    fn v10_fixture_migrates_to_current_version() {
+        let (player, map, _, name) = read_world(&fixture_folder(10)).unwrap();
+
+        assert_eq!(name, "Fixture Ten");
+        assert_eq!(player.health, 91);
+        assert!(player.status_effects.is_empty());
+        assert_eq!(map.entity_at(4, 1), Some(Entity::Enemy));
+        assert_eq!(map.entity_at(6, 1), Some(Entity::Chest));
+    }
+
+    #[test]
+    fn v11_fixture_migrates_to_current_version() {
+        let (player, _, _, name) = read_world(&fixture_folder(11)).unwrap();
+
+        assert_eq!(name, "Fixture Eleven");
+        assert_eq!(player.health, 87);
+        assert_eq!(player.status_effects.potency(StatusKind::Poison), 2);
+        assert_eq!(player.respawn_point, None);
+    }
+
+    #[test]
+    fn status_effects_survive_a_save() {
+        let save_folder = temp_save_folder("status");
+        let mut player = Player::new();
+        player
+            .status_effects
+            .apply(StatusEffect::new(StatusKind::Poison, 2, 3));
+        save_game(&player, &Map::new(30, 30, 0), &save_folder, "Sickly").unwrap();
+
+        let (loaded, _, _) = read_save(&save_folder).unwrap();
+        assert_eq!(loaded.status_effects, player.status_effects);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn entities_survive_a_save() {
+        let save_folder = temp_save_folder("entities");
+        let mut areas = Areas::default();
+        let mut game_map = Map::new(60, 60, 42);
+        let stairs = game_map.portals[1].clone();
+        game_map.move_player(&Direction::Left);
+        areas.travel(&mut game_map, stairs.x, stairs.y).unwrap();
+        let guards = game_map.entities.clone();
+        let (x, y) = (guards[0].x, guards[0].y);
+        game_map.remove_entity(x, y);
+        save_world(&Player::new(), &game_map, &areas, &save_folder, "Delver").unwrap();
+
+        let (_, loaded, _, _) = read_world(&save_folder).unwrap();
+        assert_eq!(loaded.entities, guards[1..]);
+        assert_eq!(loaded.entity_at(x, y), None);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn explored_tiles_survive_a_save() {
+        let save_folder = temp_save_folder("explored");
+        let mut game_map = Map::new(60, 60, 42);
+        let start = (game_map.player_x, game_map.player_y);
+        for _ in 0..10 {
+            game_map.move_player(&Direction::Down);
+        }
+        assert!(game_map.is_explored(start.0, start.1));
+        save_game(&Player::new(), &game_map, &save_folder, "Explorer").unwrap();
+
+        let (_, loaded, _) = read_save(&save_folder).unwrap();
+        assert_eq!(loaded.explored, game_map.explored);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn tiles_are_only_stored_in_the_map_file() {
+        let save_folder = temp_save_folder("map_once");
+        let game_map = Map::new(30, 30, 42);
+        save_game_with_backups(
+            &Player::new(),
+            &game_map,
+            &Areas::default(),
+            &save_folder,
+            "Once",
+            0,
+        )
+        .unwrap();
+
+        let raw: Value =
+            serde_json::from_str(&fs::read_to_string(save_folder.join(CHARACTER_FILE)).unwrap())
+                .unwrap();
+        assert!(raw["game_map"].get("tiles").is_none());
+        let map_file = fs::read_to_string(save_folder.join(MAP_FILE)).unwrap();
+        assert!(Map::is_encoded_map(&map_file));
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn metadata_is_written_with_each_save() {
+        let save_folder = temp_save_folder("metadata");
+        let mut player = Player::new();
+        player.play_time = 3725;
+        player.add_quest(starting_quest());
+        player.complete_quest(1);
+        let game_map = Map::new(30, 30, 42);
+        save_game_with_backups(
+            &player,
+            &game_map,
+            &Areas::default(),
+            &save_folder,
+            "Meta",
+            0,
+        )
+        .unwrap();
+
+        let metadata = read_metadata(&save_folder).unwrap();
+        assert_eq!(metadata.character_name, "Meta");
+        assert_eq!(metadata.total_level, player.total_level());
+        assert_eq!(metadata.combat_level, 1);
+        assert_eq!(metadata.play_time_display(), "1h 02m");
+        assert_eq!(
+            metadata.last_location,
+            format!("({}, {})", game_map.player_x, game_map.player_y)
+        );
+        assert_eq!(metadata.completed_quests, 1);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn metadata_is_generated_for_older_saves() {
+        let save_folder = temp_save_folder("metadata_backfill");
+        for file in [CHARACTER_FILE, MAP_FILE] {
+            fs::copy(fixture_folder(1).join(file), save_folder.join(file)).unwrap();
+        }
+
+        let metadata = read_metadata(&save_folder).unwrap();
+        assert_eq!(metadata.character_name, "Fixture One");
+        assert_eq!(metadata.total_level, 10);
+        assert!(save_folder.join(METADATA_FILE).exists());
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn migrate_drops_duplicate_v1_fields() {
+        let mut raw: Value = serde_json::from_str(
+            &fs::read_to_string(fixture_folder(1).join("character.json")).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(migrate(&mut raw).unwrap(), 1);
+        assert_eq!(raw["save_version"], SAVE_VERSION);
+        for field in [
+            "name",
+            "health",
+            "level",
+            "experience",
+            "skills",
+            "player_x",
+            "player_y",
+            "inventory",
+            "quests",
+        ] {
+            assert!(raw.get(field).is_none(), "{} should be removed", field);
+        }
+    }
+
+    #[test]
+    fn broken_saves_return_typed_errors() {
+        let save_folder = temp_save_folder("broken");
+        let game_map = Map::new(30, 30, 42);
+        save_game(&Player::new(), &game_map, &save_folder, "Broken").unwrap();
+
+        fs::remove_file(save_folder.join("map.txt")).unwrap();
+        assert!(matches!(
+            read_save(&save_folder),
+            Err(SaveError::MissingFile(_))
+        ));
+
+        save_game(&Player::new(), &game_map, &save_folder, "Broken").unwrap();
+        let map_file = fs::read_to_string(save_folder.join("map.txt")).unwrap();
+        fs::write(
+            save_folder.join("map.txt"),
+            map_file.replacen("RPGMAP 2 30 30", "RPGMAP 2 60 30", 1),
+        )
+        .unwrap();
+        assert!(matches!(
+            read_save(&save_folder),
+            Err(SaveError::MapSizeMismatch { .. })
+        ));
+
+        fs::write(save_folder.join("character.json"), "{\"player\": ").unwrap();
+        assert!(matches!(read_save(&save_folder), Err(SaveError::Parse(_))));
+
+        fs::write(
+            save_folder.join("character.json"),
+            "{\"save_version\": 999}",
+        )
+        .unwrap();
+        assert!(matches!(
+            read_save(&save_folder),
+            Err(SaveError::VersionMismatch { found: 999, .. })
+        ));
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn saves_rotate_a_limited_number_of_backups() {
+        let save_folder = temp_save_folder("rotate");
+        let mut player = Player::new();
+        let game_map = Map::new(30, 30, 42);
+
+        for health in 1..=5 {
+            player.health = health;
+            save_game_with_backups(
+                &player,
+                &game_map,
+                &Areas::default(),
+                &save_folder,
+                "Rotate",
+                2,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(list_backups(&save_folder), vec![1, 2]);
+        assert_eq!(read_save(&save_folder).unwrap().0.health, 5);
+        assert_eq!(read_backup(&save_folder, 1).unwrap().0.health, 4);
+        assert_eq!(read_backup(&save_folder, 2).unwrap().0.health, 3);
+        assert!(!save_folder.join(COMMIT_MARKER).exists());
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn restore_backup_recovers_previous_save() {
+        let save_folder = temp_save_folder("backup");
+        let mut player = Player::new();
+        let game_map = Map::new(30, 30, 42);
+
+        player.health = 11;
+        save_game_with_backups(
+            &player,
+            &game_map,
+            &Areas::default(),
+            &save_folder,
+            "Backup",
+            3,
+        )
+        .unwrap();
+        assert!(list_backups(&save_folder).is_empty());
+
+        player.health = 22;
+        save_game_with_backups(
+            &player,
+            &game_map,
+            &Areas::default(),
+            &save_folder,
+            "Backup",
+            3,
+        )
+        .unwrap();
+        fs::write(save_folder.join(CHARACTER_FILE), "garbage").unwrap();
+        assert!(read_save(&save_folder).is_err());
+
+        restore_backup(&save_folder, 1).unwrap();
+        let (loaded, _, _) = read_save(&save_folder).unwrap();
+        assert_eq!(loaded.health, 11);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+
+    #[test]
+    fn interrupted_saves_roll_forward_only_once_committed() {
+        let save_folder = temp_save_folder("interrupted");
+        let mut player = Player::new();
+        let game_map = Map::new(30, 30, 42);
+        player.health = 30;
+        let areas = Areas::default();
+        save_game_with_backups(&player, &game_map, &areas, &save_folder, "Interrupted", 1).unwrap();
+
+        // Crash after staging only the character file: the old pair stays
+        player.health = 40;
+        let staged = CharacterSave {
+            save_version: SAVE_VERSION,
+            character_name: "Interrupted".to_string(),
+            player: player.clone(),
+            game_map: game_map.clone(),
+            areas: Vec::new(),
+            current_map: OVERWORLD.to_string(),
+        };
+        let staged = serde_json::to_string(&staged).unwrap();
+        fs::write(temp_path(&save_folder, CHARACTER_FILE), &staged).unwrap();
+        assert_eq!(read_save(&save_folder).unwrap().0.health, 30);
+        assert!(!temp_path(&save_folder, CHARACTER_FILE).exists());
+
+        // Crash after the commit marker and backup rotation: the new pair wins
+        fs::write(temp_path(&save_folder, CHARACTER_FILE), &staged).unwrap();
+        fs::write(temp_path(&save_folder, MAP_FILE), game_map.serialize_map()).unwrap();
+        fs::write(save_folder.join(COMMIT_MARKER), "").unwrap();
+        rotate_backups(&save_folder, 1).unwrap();
+        assert_eq!(read_save(&save_folder).unwrap().0.health, 40);
+        assert_eq!(read_backup(&save_folder, 1).unwrap().0.health, 30);
+
+        let _ = fs::remove_dir_all(&save_folder);
+    }
+}
@@ -28,6 +28,7 @@ impl Skill {
         }
     }
 
+    #[allow(dead_code)]
     pub fn display_skill_info(&self) {
         println!(
             "Skill: {}, Level: {}, Experience: {}",
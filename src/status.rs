@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The kinds of lasting effects a fighter can be under.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum StatusKind {
+    Poison, // Loses `potency` health every turn
+    Bleed,  // Like poison, but wounds pile up
+    Stun,   // Loses their turns
+    Regen,  // Gains `potency` health every turn
+    Might,  // Hits up to `potency` harder
+    Guard,  // Adds `potency` to defense
+}
+
+impl StatusKind {
+    /// Whether applying the effect again adds to its potency rather than
+    /// refreshing it.
+    fn stacks(self) -> bool {
+        self == StatusKind::Bleed
+    }
+}
+
+impl fmt::Display for StatusKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StatusKind::Poison => "Poisoned",
+            StatusKind::Bleed => "Bleeding",
+            StatusKind::Stun => "Stunned",
+            StatusKind::Regen => "Regenerating",
+            StatusKind::Might => "Mighty",
+            StatusKind::Guard => "Guarded",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An effect lasting `turns` more turns.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub potency: i32,
+    pub turns: u32,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, potency: i32, turns: u32) -> StatusEffect {
+        StatusEffect {
+            kind,
+            potency,
+            turns,
+        }
+    }
+}
+
+/// Most a stacking effect can build up to.
+const MAX_STACKED_POTENCY: i32 = 10;
+
+/// The effects a fighter is under, at most one of each kind.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    /// Adds `effect`. An effect of a kind already in place refreshes it,
+    /// keeping the longer duration and the stronger potency; bleeding adds
+    /// its potency to the wounds already there instead.
+    pub fn apply(&mut self, effect: StatusEffect) {
+        match self
+            .0
+            .iter_mut()
+            .find(|current| current.kind == effect.kind)
+        {
+            Some(current) => {
+                current.turns = current.turns.max(effect.turns);
+                current.potency = if effect.kind.stacks() {
+                    (current.potency + effect.potency).min(MAX_STACKED_POTENCY)
+                } else {
+                    current.potency.max(effect.potency)
+                };
+            }
+            None => self.0.push(effect),
+        }
+    }
+
+    /// Potency of the effect of `kind`, or 0 without one.
+    pub fn potency(&self, kind: StatusKind) -> i32 {
+        self.0
+            .iter()
+            .find(|effect| effect.kind == kind)
+            .map_or(0, |effect| effect.potency)
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Passes a turn: every effect that changes health does so, and every
+    /// effect counts down, wearing off when it runs out. Returns the health
+    /// change of each effect that had one.
+    pub fn tick(&mut self) -> Vec<(StatusKind, i32)> {
+        let changes = self
+            .0
+            .iter()
+            .filter_map(|effect| match effect.kind {
+                StatusKind::Poison | StatusKind::Bleed => Some((effect.kind, -effect.potency)),
+                StatusKind::Regen => Some((effect.kind, effect.potency)),
+                _ => None,
+            })
+            .collect();
+        // Effects read from a save can come with no turns left at all
+        for effect in &mut self.0 {
+            effect.turns = effect.turns.saturating_sub(1);
+        }
+        self.0.retain(|effect| effect.turns > 0);
+        changes
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// "Poisoned (2), Mighty +3 (4)", or "none".
+impl fmt::Display for StatusEffects {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "none");
+        }
+        let effects: Vec<String> = self
+            .0
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::Might | StatusKind::Guard => {
+                    format!("{} +{} ({})", effect.kind, effect.potency, effect.turns)
+                }
+                _ => format!("{} ({})", effect.kind, effect.turns),
+            })
+            .collect();
+        write!(f, "{}", effects.join(", "))
+    }
+}
+
+/// What a status effect did to `target` this turn, e.g. "Poison deals 2
+/// damage to you."
+pub fn describe_tick(kind: StatusKind, change: i32, target: &str) -> String {
+    match kind {
+        StatusKind::Poison => format!("Poison deals {} damage to {}.", -change, target),
+        StatusKind::Bleed => format!("Bleeding deals {} damage to {}.", -change, target),
+        _ => format!("Regeneration restores {} health to {}.", change, target),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_refresh_or_stack() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::new(StatusKind::Poison, 2, 3));
+        effects.apply(StatusEffect::new(StatusKind::Poison, 1, 5));
+        assert_eq!(effects.potency(StatusKind::Poison), 2);
+        assert_eq!(effects.0.len(), 1);
+        assert_eq!(effects.0[0].turns, 5);
+
+        effects.apply(StatusEffect::new(StatusKind::Bleed, 2, 2));
+        effects.apply(StatusEffect::new(StatusKind::Bleed, 2, 2));
+        assert_eq!(effects.potency(StatusKind::Bleed), 4);
+        for _ in 0..10 {
+            effects.apply(StatusEffect::new(StatusKind::Bleed, 2, 2));
+        }
+        assert_eq!(effects.potency(StatusKind::Bleed), MAX_STACKED_POTENCY);
+    }
+
+    #[test]
+    fn effects_tick_and_wear_off() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::new(StatusKind::Poison, 2, 2));
+        effects.apply(StatusEffect::new(StatusKind::Regen, 3, 1));
+        effects.apply(StatusEffect::new(StatusKind::Stun, 0, 1));
+
+        assert_eq!(
+            effects.tick(),
+            vec![(StatusKind::Poison, -2), (StatusKind::Regen, 3)]
+        );
+        assert!(!effects.has(StatusKind::Stun));
+        assert_eq!(effects.tick(), vec![(StatusKind::Poison, -2)]);
+        assert!(effects.is_empty());
+        assert!(effects.tick().is_empty());
+
+        // An effect with no turns left, as an edited save might hold, just ends
+        let mut effects: StatusEffects =
+            serde_json::from_str(r#"[{"kind":"Poison","potency":2,"turns":0}]"#).unwrap();
+        assert_eq!(effects.tick(), vec![(StatusKind::Poison, -2)]);
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn effects_are_listed_with_their_turns_left() {
+        let mut effects = StatusEffects::default();
+        assert_eq!(effects.to_string(), "none");
+        effects.apply(StatusEffect::new(StatusKind::Poison, 2, 2));
+        effects.apply(StatusEffect::new(StatusKind::Might, 3, 4));
+        assert_eq!(effects.to_string(), "Poisoned (2), Mighty +3 (4)");
+    }
+}
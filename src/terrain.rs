@@ -0,0 +1,301 @@
+use crate::map::Tile;
+use serde::{Deserialize, Serialize};
+
+/// Distance in tiles between the lattice points of the broadest elevation and
+/// moisture noise layers, roughly the size of a biome.
+const ELEVATION_SCALE: f64 = 96.0;
+const MOISTURE_SCALE: f64 = 64.0;
+const NOISE_OCTAVES: u32 = 4;
+
+// Salts so elevation, moisture and the per-tile rolls don't share noise
+const ELEVATION_SALT: u64 = 0x454C_4556;
+const MOISTURE_SALT: u64 = 0x4D4F_4953;
+const DETAIL_SALT: u64 = 0x4445_5441;
+const RIVER_SALT: u64 = 0x5249_5645;
+const FISHING_SALT: u64 = 0x4649_5348;
+
+/// Rivers follow the middle contour of their own noise layer, so they wind
+/// across the world without ever ending.
+const RIVER_SCALE: f64 = 160.0;
+const RIVER_WIDTH: f64 = 0.008;
+
+/// Share of shore water tiles that are fishing spots.
+const FISHING_SPOT_CHANCE: f64 = 0.08;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum Biome {
+    Lake,
+    Beach,
+    Grassland,
+    Forest,
+    Hills,
+    Mountains,
+}
+
+impl Biome {
+    pub fn name(&self) -> &str {
+        match self {
+            Biome::Lake => "Lake",
+            Biome::Beach => "Beach",
+            Biome::Grassland => "Grassland",
+            Biome::Forest => "Forest",
+            Biome::Hills => "Rocky Hills",
+            Biome::Mountains => "Mountains",
+        }
+    }
+}
+
+/// Hash of a world position, mixed so neighbouring tiles look unrelated.
+pub fn position_hash(seed: u64, x: usize, y: usize) -> u64 {
+    let mut hash = seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    hash ^= (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    hash ^ (hash >> 33)
+}
+
+/// `position_hash` as a number in `0.0..1.0`.
+pub fn position_roll(seed: u64, x: usize, y: usize) -> f64 {
+    (position_hash(seed, x, y) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Smoothly interpolated random values on an integer lattice, in `0.0..1.0`.
+fn value_noise(seed: u64, x: f64, y: f64) -> f64 {
+    let (left, top) = (x.floor(), y.floor());
+    let (ix, iy) = (left as usize, top as usize);
+    // Smoothstep, so the lattice doesn't show as straight edges
+    let fade = |t: f64| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (fade(x - left), fade(y - top));
+    let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+
+    let upper = lerp(
+        position_roll(seed, ix, iy),
+        position_roll(seed, ix + 1, iy),
+        tx,
+    );
+    let lower = lerp(
+        position_roll(seed, ix, iy + 1),
+        position_roll(seed, ix + 1, iy + 1),
+        tx,
+    );
+    lerp(upper, lower, ty)
+}
+
+/// Several layers of value noise, each twice as fine and half as strong as
+/// the one before, in `0.0..1.0`.
+pub fn fractal_noise(seed: u64, x: usize, y: usize, scale: f64) -> f64 {
+    let (mut total, mut weight) = (0.0, 0.0);
+    let (mut amplitude, mut frequency) = (1.0, 1.0 / scale);
+    for octave in 0..NOISE_OCTAVES {
+        let layer_seed = seed.wrapping_add(octave as u64 * 0x1_0000_0001);
+        total += amplitude * value_noise(layer_seed, x as f64 * frequency, y as f64 * frequency);
+        weight += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / weight
+}
+
+/// Biome at `(x, y)`, from an elevation and a moisture noise layer.
+pub fn biome_at(seed: u64, x: usize, y: usize) -> Biome {
+    let elevation = fractal_noise(seed ^ ELEVATION_SALT, x, y, ELEVATION_SCALE);
+    if elevation < 0.36 {
+        return Biome::Lake;
+    } else if elevation < 0.39 {
+        return Biome::Beach;
+    } else if elevation > 0.68 {
+        return Biome::Mountains;
+    } else if elevation > 0.61 {
+        return Biome::Hills;
+    }
+
+    let moisture = fractal_noise(seed ^ MOISTURE_SALT, x, y, MOISTURE_SCALE);
+    if moisture > 0.53 {
+        Biome::Forest
+    } else {
+        Biome::Grassland
+    }
+}
+
+/// Whether a river runs through `(x, y)`. Rivers cut through every biome
+/// except mountains.
+pub fn river_at(seed: u64, x: usize, y: usize) -> bool {
+    let river_noise =
+        |x: usize, y: usize| fractal_noise(seed ^ RIVER_SALT, x, y, RIVER_SCALE) - 0.5;
+    let here = river_noise(x, y);
+    // Neighbouring tiles never differ by this much, so nothing nearby can
+    // cross the contour
+    if here.abs() > 0.05 {
+        return false;
+    }
+    // Tiles just below the contour next to one above it keep the river
+    // connected where the noise is steep and the band gets thin
+    let on_contour = here.abs() < RIVER_WIDTH
+        || (here < 0.0
+            && [
+                (x + 1, y),
+                (x, y + 1),
+                (x.wrapping_sub(1), y),
+                (x, y.wrapping_sub(1)),
+            ]
+            .iter()
+            .any(|&(nx, ny)| nx != usize::MAX && ny != usize::MAX && river_noise(nx, ny) >= 0.0));
+    on_contour && biome_at(seed, x, y) != Biome::Mountains
+}
+
+/// Whether `(x, y)` is lake or river water.
+pub fn water_at(seed: u64, x: usize, y: usize) -> bool {
+    biome_at(seed, x, y) == Biome::Lake || river_at(seed, x, y)
+}
+
+/// The untouched tile at `(x, y)`. It only depends on the arguments, so
+/// tiles can be generated in any order and unmodified chunks never need
+/// saving.
+pub fn generate_tile(seed: u64, x: usize, y: usize) -> Tile {
+    if water_at(seed, x, y) {
+        return water_tile(seed, x, y);
+    }
+    let roll = position_roll(seed ^ DETAIL_SALT, x, y);
+    match biome_at(seed, x, y) {
+        Biome::Lake => Tile::Water,
+        Biome::Beach => pick(roll, &[(0.97, Tile::Sand)], Tile::Rock),
+        Biome::Grassland => pick(
+            roll,
+            &[(0.04, Tile::Tree), (0.05, Tile::Rock), (0.75, Tile::Grass)],
+            Tile::Empty,
+        ),
+        Biome::Forest => pick(
+            roll,
+            &[(0.45, Tile::Tree), (0.80, Tile::Grass)],
+            Tile::Empty,
+        ),
+        Biome::Hills => pick(
+            roll,
+            &[(0.25, Tile::Rock), (0.30, Tile::Tree), (0.55, Tile::Grass)],
+            Tile::Empty,
+        ),
+        Biome::Mountains => pick(roll, &[(0.85, Tile::Mountain)], Tile::Rock),
+    }
+}
+
+/// Water, or now and then a fishing spot where the water meets land.
+fn water_tile(seed: u64, x: usize, y: usize) -> Tile {
+    if position_roll(seed ^ FISHING_SALT, x, y) >= FISHING_SPOT_CHANCE {
+        return Tile::Water;
+    }
+    let neighbours = [
+        (x.wrapping_sub(1), y),
+        (x + 1, y),
+        (x, y.wrapping_sub(1)),
+        (x, y + 1),
+    ];
+    let on_shore = neighbours
+        .iter()
+        .any(|&(nx, ny)| nx != usize::MAX && ny != usize::MAX && !water_at(seed, nx, ny));
+    if on_shore {
+        Tile::FishingSpot
+    } else {
+        Tile::Water
+    }
+}
+
+/// First tile whose cumulative threshold is above `roll`, else `otherwise`.
+fn pick(roll: f64, thresholds: &[(f64, Tile)], otherwise: Tile) -> Tile {
+    thresholds
+        .iter()
+        .find(|(threshold, _)| roll < *threshold)
+        .map_or(otherwise, |&(_, tile)| tile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn noise_is_smooth_and_in_range() {
+        for y in 0..200 {
+            for x in 0..200 {
+                let here = fractal_noise(3, x, y, ELEVATION_SCALE);
+                let next = fractal_noise(3, x + 1, y, ELEVATION_SCALE);
+                assert!((0.0..1.0).contains(&here));
+                assert!((here - next).abs() < 0.1, "noise jumps at ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn every_biome_shows_up_in_a_large_area() {
+        let mut seen: HashMap<Biome, usize> = HashMap::new();
+        for y in (0..2000).step_by(4) {
+            for x in (0..2000).step_by(4) {
+                *seen.entry(biome_at(11, x, y)).or_default() += 1;
+            }
+        }
+        for biome in [
+            Biome::Lake,
+            Biome::Beach,
+            Biome::Grassland,
+            Biome::Forest,
+            Biome::Hills,
+            Biome::Mountains,
+        ] {
+            assert!(seen.get(&biome).copied().unwrap_or(0) > 0, "no {:?}", biome);
+        }
+    }
+
+    #[test]
+    fn forests_are_denser_than_grassland() {
+        let tree_share = |biome: Biome| {
+            let tiles: Vec<Tile> = (0..400)
+                .flat_map(|y| (0..400).map(move |x| (x, y)))
+                .filter(|&(x, y)| biome_at(5, x, y) == biome)
+                .map(|(x, y)| generate_tile(5, x, y))
+                .collect();
+            tiles.iter().filter(|&&tile| tile == Tile::Tree).count() as f64 / tiles.len() as f64
+        };
+        assert!(tree_share(Biome::Forest) > 0.3);
+        assert!(tree_share(Biome::Grassland) < 0.1);
+    }
+
+    #[test]
+    fn rivers_are_connected() {
+        let mut river_tiles = 0;
+        for y in 1..600 {
+            for x in 1..600 {
+                if !river_at(7, x, y) {
+                    continue;
+                }
+                river_tiles += 1;
+                // Rivers only end where they rise in the mountains
+                let continues = (y - 1..=y + 1)
+                    .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                    .any(|(nx, ny)| {
+                        (nx, ny) != (x, y)
+                            && (water_at(7, nx, ny) || biome_at(7, nx, ny) == Biome::Mountains)
+                    });
+                assert!(continues, "lone river tile at ({}, {})", x, y);
+            }
+        }
+        assert!(river_tiles > 100);
+    }
+
+    #[test]
+    fn fishing_spots_sit_on_the_shore() {
+        let mut spots = 0;
+        for y in 1..600 {
+            for x in 1..600 {
+                if generate_tile(7, x, y) != Tile::FishingSpot {
+                    continue;
+                }
+                spots += 1;
+                assert!(water_at(7, x, y));
+                let land = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+                assert!(land.iter().any(|&(nx, ny)| !water_at(7, nx, ny)));
+            }
+        }
+        assert!(spots > 0);
+    }
+}
@@ -6,9 +6,13 @@ use std::time::Duration;
 use crossterm::event::{self, Event, KeyCode, KeyEvent};
 
 // Ensure you have access to these structs and enums
+use crate::autosave::AutosaveTracker;
 use crate::map::{Map, Direction};
 use crate::player::Player;
+use crate::enemy::encounter_table;
+use crate::status::describe_tick;
 
+#[allow(dead_code)]
 pub struct MovementWeights {
     pub same_direction: u32,
     pub away_from_campfire: u32,
@@ -23,7 +27,7 @@ pub struct MovementWeights {
     pub right: u32,
 }
 
-pub fn faf(player: &mut Player, game_map: &mut Map) -> bool {
+pub fn faf(player: &mut Player, game_map: &mut Map, autosave: &mut AutosaveTracker) -> bool {
     // Initialize weights (adjust as needed)
     let weights = MovementWeights {
         same_direction: 128,
@@ -66,14 +70,25 @@ pub fn faf(player: &mut Player, game_map: &mut Map) -> bool {
         // Calculate the next direction using movement weights
         let direction = weighted_random_direction(&mut rng, &weights, prev_direction, game_map);
 
-        // Move the player
-        game_map.move_player(&direction);
+        // Move the player. Real steps pass time, count towards autosaving and
+        // tick status effects like walking by hand, and poison can end the walk
+        if game_map.move_player(&direction) {
+            game_map.advance_time(1);
+            autosave.record_move();
+            for (kind, change) in player.tick_status_effects() {
+                println!("{}", describe_tick(kind, change, "you"));
+            }
+            if player.health <= 0 {
+                break;
+            }
+        }
         prev_direction = direction;
 
-        // Check for enemy encounter
-        if should_encounter_enemy(1) { // Adjust the encounter rate as needed
+        // Check for enemy encounter, as often as the biome's encounter table says
+        let encounters = encounter_table(game_map.biome(game_map.player_x, game_map.player_y));
+        if game_map.is_overworld() && should_encounter_enemy(encounters.chance) {
             println!("Enemy encountered! Stopping automatic movement.");
-            player.in_combat = true;
+            player.enter_combat();
             return true; // Indicate that combat should be initiated
         }
 
@@ -106,7 +121,7 @@ pub fn weighted_random_direction(
     rng: &mut impl Rng,
     weights: &MovementWeights,
     prev_direction: Direction,
-    game_map: &Map,
+    _game_map: &Map,
 ) -> Direction {
     // Collect all possible directions with their associated weights
     let mut directions = vec![
@@ -143,6 +158,5 @@ pub fn weighted_random_direction(
 
 pub fn should_encounter_enemy(chance: u8) -> bool {
     let mut rng = rand::thread_rng();
-    let encounter = rng.gen_range(0..100) < chance;
-    encounter
+    rng.gen_range(0..100) < chance
 }
@@ -0,0 +1,191 @@
+use crate::dungeon::{floor_of, generate_floor, DUNGEON_FLOORS};
+use crate::map::{Map, Portal, OVERWORLD, TOWN};
+use std::collections::BTreeMap;
+
+/// Name of the inn inside the starting town.
+pub const INN: &str = "inn";
+
+const TOWN_LAYOUT: [&str; 14] = [
+    "==============================",
+    "=t\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"\"t=",
+    "=\"=======\"\"\"\"\"\"\"\"\"\"\"\"=======\"=",
+    "=\"=.....=\"\"\"\"t\"\"\"\"t\"\"=.....=\"=",
+    "=\"=.....=\"\"\"\"\"\"\"\"\"\"\"\"=.....=\"=",
+    "=\"===+===\"\"\"\"\"\"\"\"\"\"\"\"===+===\"=",
+    "=\"\"\"\"\"\"\"\"\"..........\"\"\"\"\"\"\"\"\"=",
+    "=\"\"\"\"\"\"\"\"\"....#.....\"\"\"\"\"\"\"\"\"=",
+    "=\"\"\"\"\"\"\"\"\"..........\"\"\"\"\"\"\"\"\"=",
+    "=\"=======\"\"\"\"\"\"\"\"\"\"\"\"=======\"=",
+    "=\"=.....=\"\"\"\"\"\"\"\"\"\"\"\"=.....=\"=",
+    "=\"===+===\"\"\"\"\"\"\"\"\"\"\"\"===+===\"=",
+    "=t\"\"\"\"\"\"\"\"\"\"\"\"P\"\"\"\"\"\"\"\"\"\"\"\"\"t=",
+    "==============+===============",
+];
+
+const INN_LAYOUT: [&str; 6] = [
+    "============",
+    "=..........=",
+    "=.#........=",
+    "=..........=",
+    "=....P.....=",
+    "=====+======",
+];
+
+/// The maps of a save the player is not in right now, by name.
+#[derive(Debug, Default, Clone)]
+pub struct Areas {
+    maps: BTreeMap<String, Map>,
+}
+
+impl Areas {
+    pub fn insert(&mut self, map: Map) {
+        self.maps.insert(map.name.clone(), map);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Map> {
+        self.maps.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Map> {
+        self.maps.values()
+    }
+
+    /// Takes the player through the door at `(x, y)` on `current`. The map
+    /// they leave is kept here and `current` becomes the map they arrive on,
+    /// which is built the first time anyone goes there. Returns the name of
+    /// that map, or `None` when the door doesn't lead anywhere.
+    pub fn travel(&mut self, current: &mut Map, x: usize, y: usize) -> Option<String> {
+        let portal = current.portal_at(x, y)?.clone();
+        if !self.go_to(current, &portal.target) {
+            return None;
+        }
+        let (arrival_x, arrival_y) = portal
+            .arrival
+            .unwrap_or((current.player_x, current.player_y));
+        current.place_player(arrival_x, arrival_y);
+        Some(portal.target)
+    }
+
+    /// Makes the map named `name` the current one without going through a
+    /// door, leaving the player where they last stood on it. Returns whether
+    /// there is such a map.
+    pub fn go_to(&mut self, current: &mut Map, name: &str) -> bool {
+        let Some(mut target) = self
+            .maps
+            .remove(name)
+            .or_else(|| build_area(name, current.seed))
+        else {
+            return false;
+        };
+
+        // All maps share one clock, so whatever regrew meanwhile grows back now
+        if target.world_time < current.world_time {
+            target.advance_time(current.world_time - target.world_time);
+        }
+        let previous = std::mem::replace(current, target);
+        self.insert(previous);
+        true
+    }
+}
+
+/// The built in or generated map named `name` in the world with `seed`, as
+/// it looks before anyone visits it.
+pub fn build_area(name: &str, seed: u64) -> Option<Map> {
+    let portal = |x, y, target: &str| Portal {
+        x,
+        y,
+        target: target.to_string(),
+        arrival: None,
+    };
+    let area = match name {
+        TOWN => Some(Map::from_layout(
+            TOWN,
+            &TOWN_LAYOUT,
+            vec![portal(14, 13, OVERWORLD), portal(5, 5, INN)],
+        )),
+        INN => Some(Map::from_layout(INN, &INN_LAYOUT, vec![portal(5, 5, TOWN)])),
+        _ => match floor_of(name) {
+            Some(floor) if (1..=DUNGEON_FLOORS).contains(&floor) => {
+                Some(generate_floor(seed, floor))
+            }
+            _ => None,
+        },
+    };
+    // Every map of a world shares its seed, so a save knows it from any map
+    area.map(|mut map| {
+        map.seed = seed;
+        map
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Direction, Tile};
+
+    /// Walks into the door the player is facing, like the game loop does.
+    fn walk_through(areas: &mut Areas, map: &mut Map, facing: Direction) -> Option<String> {
+        let (x, y, tile) = map.faced_tile(facing)?;
+        assert_eq!(tile, Tile::Door);
+        areas.travel(map, x, y)
+    }
+
+    #[test]
+    fn layouts_are_rectangular() {
+        for layout in [&TOWN_LAYOUT[..], &INN_LAYOUT[..]] {
+            assert!(layout.iter().all(|row| row.len() == layout[0].len()));
+        }
+    }
+
+    #[test]
+    fn doors_lead_to_town_and_back() {
+        let mut areas = Areas::default();
+        let mut map = Map::new(60, 60, 42);
+        let start = (map.player_x, map.player_y);
+
+        map.move_player(&Direction::Right);
+        assert_eq!(
+            walk_through(&mut areas, &mut map, Direction::Right).as_deref(),
+            Some(TOWN)
+        );
+        assert_eq!(map.name, TOWN);
+        assert_eq!((map.player_x, map.player_y), (14, 12));
+        assert!(areas.maps.contains_key(OVERWORLD));
+
+        // Into the inn and out again lands in front of its door
+        map.place_player(5, 6);
+        walk_through(&mut areas, &mut map, Direction::Up);
+        assert_eq!(map.name, INN);
+        assert_eq!(map.tile(2, 2), Tile::Campfire);
+        walk_through(&mut areas, &mut map, Direction::Down);
+        assert_eq!(
+            (map.name.as_str(), map.player_x, map.player_y),
+            (TOWN, 5, 6)
+        );
+
+        map.place_player(14, 12);
+        walk_through(&mut areas, &mut map, Direction::Down);
+        assert!(map.is_overworld());
+        assert_eq!((map.player_x, map.player_y), (start.0 + 1, start.1));
+    }
+
+    #[test]
+    fn locked_doors_go_nowhere() {
+        let mut areas = Areas::default();
+        let mut map = build_area(TOWN, 0).unwrap();
+        map.place_player(24, 6);
+        assert_eq!(walk_through(&mut areas, &mut map, Direction::Up), None);
+        assert_eq!(map.name, TOWN);
+        assert!(!areas.maps.contains_key(TOWN));
+    }
+
+    #[test]
+    fn maps_share_the_world_clock() {
+        let mut areas = Areas::default();
+        let mut map = build_area(TOWN, 0).unwrap();
+        map.advance_time(40);
+        map.place_player(5, 6);
+        walk_through(&mut areas, &mut map, Direction::Up);
+        assert_eq!(map.world_time, 40);
+    }
+}